@@ -51,11 +51,65 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_padding_is_valid() {
+        assert!(padding_is_valid(b"ICE ICE BABY\x04\x04\x04\x04", 16));
+        assert!(!padding_is_valid(b"ICE ICE BABY\x05\x05\x05\x05", 16));
+        assert!(!padding_is_valid(b"ICE ICE BABY\x01\x02\x03\x04", 16));
+        assert!(!padding_is_valid(b"ICE ICE BABY\x04\x04\x04\x00", 16));
+    }
+
+    #[test]
+    fn test_padding_pkcs7_aligned() -> Result<()> {
+        let mut data = b"YELLOW SUBMARINE".to_vec();
+        padding_pkcs7(&mut data, 16)?;
+
+        assert_eq!(data.len(), 32);
+        assert_eq!(&data[16..], &[16u8; 16]);
+        assert!(padding_is_valid(&data, 16));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cbc_256() -> Result<()> {
+        let plain = b"Longer keys go through the same block code";
+        let key = b"YELLOW SUBMARINEYELLOW SUBMARINE";
+        let iv = b"ABCDEF GHIJKLMNO";
+
+        let mut padded_in = plain.to_vec();
+        padding_pkcs7(&mut padded_in, 16)?;
+
+        let cipher = encrypt_aes_cbc(plain, key, iv)?;
+        assert_ne!(cipher, encrypt_aes_cbc(plain, &key[..16], iv)?);
+        assert_eq!(decrypt_aes_cbc(&cipher, key, iv)?, padded_in);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ctr() -> Result<()> {
+        // Set 3 challenge 18
+        let cipher = hex::string_to_vec_u8(
+            "2fbee76bf9eb16c2afca777a1f33a81bb1874cb5ec4d5bbdaaf63fdacc8b5f384fc1ecb23132542eeffafe45d7d0a4afa0e2d215",
+        )?;
+        let clear = decrypt_aes_ctr(&cipher, b"YELLOW SUBMARINE", 0)?;
+
+        assert_eq!(
+            clear,
+            b"Yo, VIP Let's kick it Ice, Ice, baby Ice, Ice, baby "
+        );
+        assert_eq!(encrypt_aes_ctr(&clear, b"YELLOW SUBMARINE", 0)?, cipher);
+
+        Ok(())
+    }
 }
 
 pub enum MODE {
     ECB,
     CBC,
+    CTR,
 }
 
 impl PartialEq for MODE {
@@ -63,6 +117,7 @@ impl PartialEq for MODE {
         match (self, other) {
             (MODE::ECB, MODE::ECB) => true,
             (MODE::CBC, MODE::CBC) => true,
+            (MODE::CTR, MODE::CTR) => true,
             _ => false,
         }
     }
@@ -73,6 +128,7 @@ impl fmt::Debug for MODE {
         match self {
             MODE::CBC => write!(f, "CBC Mode"),
             MODE::ECB => write!(f, "ECB Mode"),
+            MODE::CTR => write!(f, "CTR Mode"),
         }
     }
 }
@@ -82,6 +138,7 @@ impl fmt::Display for MODE {
         match self {
             MODE::CBC => write!(f, "CBC Mode"),
             MODE::ECB => write!(f, "ECB Mode"),
+            MODE::CTR => write!(f, "CTR Mode"),
         }
     }
 }
@@ -100,11 +157,9 @@ fn do_xor(left: &[u8], right: &[u8]) -> Result<Vec<u8>> {
     Ok(out_bytes)
 }
 
+/// Pads to a multiple of `block_size`, adding a whole block of padding to aligned input so the
+/// padding can always be removed unambiguously.
 pub fn padding_pkcs7(input: &mut Vec<u8>, block_size: usize) -> Result<()> {
-    if (input.len() % block_size) == 0 {
-        return Ok(());
-    }
-
     let pad_value = (block_size - (input.len() % block_size)) as u8;
 
     for _ in 0..pad_value {
@@ -126,11 +181,11 @@ pub fn padding_is_valid(data: &[u8], block_size: usize) -> bool {
         return false;
     }
 
-    if data.len() < last_byte as usize {
+    if last_byte == 0 || last_byte as usize > block_size || data.len() < last_byte as usize {
         return false;
     }
 
-    for i in 0..last_byte as usize {
+    for i in 1..=last_byte as usize {
         // If invalid byte in padding
         if data[data.len() - i] != last_byte {
             return false;
@@ -154,22 +209,30 @@ pub fn unpad_pkcs7(data: &mut Vec<u8>, block_size: usize) -> Result<()> {
     Ok(())
 }
 
-pub fn decrypt_aes_128_block(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+/// Picks the ECB cipher matching the key length: AES-128, AES-192 or AES-256.
+fn cipher_for_key(key: &[u8]) -> Result<openssl::symm::Cipher> {
+    match key.len() {
+        16 => Ok(openssl::symm::Cipher::aes_128_ecb()),
+        24 => Ok(openssl::symm::Cipher::aes_192_ecb()),
+        32 => Ok(openssl::symm::Cipher::aes_256_ecb()),
+        n => Err(format!("Invalid AES key size, should be 16, 24 or 32, is {}.", n).into()),
+    }
+}
+
+pub fn decrypt_aes_block(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     if input.len() != 16 {
         panic!("Input length should be 16, is {}.", input.len());
     }
 
-    let padding = encrypt_aes_128_block(&[16u8; 16], key)?;
+    let padding = encrypt_aes_block(&[16u8; 16], key)?;
     let mut vec_input = input.to_vec();
     vec_input.extend_from_slice(&padding);
-    let out = openssl::symm::decrypt(openssl::symm::Cipher::aes_128_ecb(), key, None, &vec_input)?;
-
-    // out.truncate(16);
+    let out = openssl::symm::decrypt(cipher_for_key(key)?, key, None, &vec_input)?;
 
     Ok(out)
 }
 
-pub fn decrypt_aes_128_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+pub fn decrypt_aes_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     if input.len() % 16 != 0 {
         panic!(
             "Cipher length should be a multiple of 16, is {}.",
@@ -179,52 +242,76 @@ pub fn decrypt_aes_128_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
 
     let mut out = Vec::new();
 
-    for block in input.to_vec().chunks(16) {
-        out.extend_from_slice(&decrypt_aes_128_block(block, key)?);
+    for block in input.chunks(16) {
+        out.extend_from_slice(&decrypt_aes_block(block, key)?);
     }
 
     Ok(out)
 }
 
-pub fn encrypt_aes_128_block(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+pub fn encrypt_aes_block(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     if input.len() != 16 {
         panic!("Input length should be 16, is {}.", input.len());
     }
 
-    let mut out = openssl::symm::encrypt(openssl::symm::Cipher::aes_128_ecb(), key, None, input)?;
+    let mut out = openssl::symm::encrypt(cipher_for_key(key)?, key, None, input)?;
 
     out.truncate(16);
 
     Ok(out)
 }
 
-pub fn encrypt_aes_128_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+pub fn encrypt_aes_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     let mut vec_input = input.to_vec();
     padding_pkcs7(&mut vec_input, 16)?;
+
+    encrypt_aes_ecb_no_padding(&vec_input, key)
+}
+
+/// ECB encryption of already block aligned input, for callers applying their own padding.
+pub fn encrypt_aes_ecb_no_padding(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    if !input.len().is_multiple_of(16) {
+        panic!(
+            "Input length should be a multiple of 16, is {}.",
+            input.len()
+        );
+    }
+
     let mut out = Vec::new();
 
-    for block in vec_input.chunks(16) {
-        out.extend_from_slice(&encrypt_aes_128_block(block, key)?);
+    for block in input.chunks(16) {
+        out.extend_from_slice(&encrypt_aes_block(block, key)?);
     }
 
     Ok(out)
 }
 
-pub fn encrypt_aes_128_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+pub fn encrypt_aes_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    let mut vec_input = input.to_vec();
+    padding_pkcs7(&mut vec_input, 16)?;
+
+    encrypt_aes_cbc_no_padding(&vec_input, key, iv)
+}
+
+/// CBC encryption of already block aligned input, for callers applying their own padding.
+pub fn encrypt_aes_cbc_no_padding(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     if iv.len() != 16 {
         panic!("Invalid IV size, should be 16, is {}.", iv.len());
     }
 
-    let mut vec_input = input.to_vec();
-    padding_pkcs7(&mut vec_input, 16)?;
+    if !input.len().is_multiple_of(16) {
+        panic!(
+            "Input length should be a multiple of 16, is {}.",
+            input.len()
+        );
+    }
+
     let mut cipher = Vec::new();
     let mut previous = iv.to_vec();
     let mut current;
 
-    println!("vec_input len: {}", vec_input.len());
-
-    for block in vec_input.chunks(16) {
-        current = encrypt_aes_128_ecb(&do_xor(&previous, block)?, key)?;
+    for block in input.chunks(16) {
+        current = encrypt_aes_block(&do_xor(&previous, block)?, key)?;
         cipher.extend_from_slice(&current);
 
         previous = current;
@@ -233,7 +320,7 @@ pub fn encrypt_aes_128_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8
     Ok(cipher)
 }
 
-pub fn decrypt_aes_128_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+pub fn decrypt_aes_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
     if iv.len() != 16 {
         panic!("Invalid IV size, should be 16, is {}.", iv.len());
     }
@@ -249,8 +336,8 @@ pub fn decrypt_aes_128_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8
     let mut current;
     let mut previous = iv.to_vec();
 
-    for block in input.to_vec().chunks(16) {
-        current = do_xor(&previous, &decrypt_aes_128_ecb(block, key)?)?;
+    for block in input.chunks(16) {
+        current = do_xor(&previous, &decrypt_aes_block(block, key)?)?;
         clear.extend_from_slice(&current);
 
         previous = block.to_vec();
@@ -259,8 +346,58 @@ pub fn decrypt_aes_128_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8
     Ok(clear)
 }
 
+/// Keystream block `counter` of CTR mode: the nonce then the counter, both as 64 bits little
+/// endian, encrypted.
+fn ctr_keystream_block(key: &[u8], nonce: u64, counter: u64) -> Result<Vec<u8>> {
+    let mut block = nonce.to_le_bytes().to_vec();
+    block.extend_from_slice(&counter.to_le_bytes());
+
+    encrypt_aes_block(&block, key)
+}
+
+/// CTR mode, XORing the input with the keystream. Inputs of any length, no padding.
+pub fn encrypt_aes_ctr(input: &[u8], key: &[u8], nonce: u64) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(input.len());
+
+    for (counter, chunk) in input.chunks(16).enumerate() {
+        let keystream = ctr_keystream_block(key, nonce, counter as u64)?;
+        out.extend(chunk.iter().zip(keystream).map(|(c, k)| c ^ k));
+    }
+
+    Ok(out)
+}
+
+/// Same operation as `encrypt_aes_ctr`.
+pub fn decrypt_aes_ctr(input: &[u8], key: &[u8], nonce: u64) -> Result<Vec<u8>> {
+    encrypt_aes_ctr(input, key, nonce)
+}
+
+pub fn decrypt_aes_128_block(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    decrypt_aes_block(input, key)
+}
+
+pub fn decrypt_aes_128_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    decrypt_aes_ecb(input, key)
+}
+
+pub fn encrypt_aes_128_block(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    encrypt_aes_block(input, key)
+}
+
+pub fn encrypt_aes_128_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
+    encrypt_aes_ecb(input, key)
+}
+
+pub fn encrypt_aes_128_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    encrypt_aes_cbc(input, key, iv)
+}
+
+pub fn decrypt_aes_128_cbc(input: &[u8], key: &[u8], iv: &[u8]) -> Result<Vec<u8>> {
+    decrypt_aes_cbc(input, key, iv)
+}
+
 pub fn blocks_and_padding(len: usize, bs: usize) -> (usize, usize) {
-    let blocks = len + bs - 1 / bs;
+    let blocks = len.div_ceil(bs);
     let padding = blocks * bs - len;
 
     (blocks, padding)
@@ -0,0 +1,111 @@
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+pub fn string_to_vec_u8(s: &str) -> Result<Vec<u8>> {
+    let data = s.trim_end_matches('=');
+    let padded = data.len() != s.len();
+
+    if padded && !s.len().is_multiple_of(8) {
+        return Err(format!(
+            "Padded base32 string length must be a multiple of 8, is: {}",
+            s.len()
+        )
+        .into());
+    }
+
+    // Only these remainders can come out of whole bytes
+    if ![0, 2, 4, 5, 7].contains(&(data.len() % 8)) {
+        return Err(format!("Invalid base32 string length: {}", data.len()).into());
+    }
+
+    // Padding only completes the final quantum
+    if padded && s.len() - data.len() != (8 - data.len() % 8) % 8 {
+        return Err(format!(
+            "Invalid base32 padding length {} for {} characters",
+            s.len() - data.len(),
+            data.len()
+        )
+        .into());
+    }
+
+    let mut out_vec = Vec::with_capacity(data.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in data.chars() {
+        buffer = (buffer << 5) | b32char_to_u8(c)? as u32;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            out_vec.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    if buffer != 0 {
+        return Err("Input not padded with zeros.".into());
+    }
+
+    Ok(out_vec)
+}
+
+fn b32char_to_u8(c: char) -> Result<u8> {
+    match c {
+        'A'..='Z' => Ok(c as u8 - b'A'),
+        'a'..='z' => Ok(c as u8 - b'a'),
+        '2'..='7' => Ok(26 + (c as u8 - b'2')),
+        _ => Err(format!("Invalid base32 character {}", c).into()),
+    }
+}
+
+pub fn vec_u8_to_string(vec: Vec<u8>) -> String {
+    let mut out_string = String::with_capacity(vec.len().div_ceil(5) * 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for b in vec {
+        buffer = (buffer << 8) | b as u32;
+        bits += 8;
+
+        while bits >= 5 {
+            bits -= 5;
+            out_string.push(ALPHABET[((buffer >> bits) & 0x1f) as usize] as char);
+        }
+        buffer &= (1 << bits) - 1;
+    }
+
+    if bits > 0 {
+        out_string.push(ALPHABET[((buffer << (5 - bits)) & 0x1f) as usize] as char);
+    }
+
+    while !out_string.len().is_multiple_of(8) {
+        out_string.push('=');
+    }
+
+    out_string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_to_vec_u8() -> Result<()> {
+        assert_eq!(b"foobar".to_vec(), string_to_vec_u8("MZXW6YTBOI======")?);
+        // TOTP secrets are usually given lowercase and unpadded
+        assert_eq!(b"foob".to_vec(), string_to_vec_u8("mzxw6yq")?);
+        assert!(string_to_vec_u8("MZXW6YQ==").is_err());
+        assert!(string_to_vec_u8("MZXW6YQ=========").is_err());
+        assert!(string_to_vec_u8("MZXW6YTB========").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vec_u8_to_string() {
+        assert_eq!(vec_u8_to_string(b"f".to_vec()), "MY======");
+        assert_eq!(vec_u8_to_string(b"foobar".to_vec()), "MZXW6YTBOI======");
+    }
+}
@@ -0,0 +1,93 @@
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+/// Bitcoin alphabet, without the look-alikes 0, O, I and l.
+const ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+pub fn string_to_vec_u8(s: &str) -> Result<Vec<u8>> {
+    // Each leading '1' stands for a leading zero byte
+    let zeros = s.chars().take_while(|&c| c == '1').count();
+
+    // Big endian base 256 digits of the number
+    let mut digits: Vec<u8> = Vec::with_capacity(s.len());
+    for c in s.chars().skip(zeros) {
+        let mut carry = b58char_to_u8(c)? as u32;
+        for d in digits.iter_mut().rev() {
+            carry += (*d as u32) * 58;
+            *d = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            digits.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+
+    let mut out_vec = vec![0u8; zeros];
+    out_vec.extend(digits);
+
+    Ok(out_vec)
+}
+
+fn b58char_to_u8(c: char) -> Result<u8> {
+    match ALPHABET.iter().position(|&a| a as char == c) {
+        Some(i) => Ok(i as u8),
+        None => Err(format!("Invalid base58 character {}", c).into()),
+    }
+}
+
+pub fn vec_u8_to_string(vec: Vec<u8>) -> String {
+    let zeros = vec.iter().take_while(|&&b| b == 0).count();
+
+    // Big endian base 58 digits of the number
+    let mut digits: Vec<u8> = Vec::with_capacity(vec.len() * 138 / 100 + 1);
+    for &b in &vec[zeros..] {
+        let mut carry = b as u32;
+        for d in digits.iter_mut().rev() {
+            carry += (*d as u32) << 8;
+            *d = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.insert(0, (carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let mut out_string = "1".repeat(zeros);
+    out_string.extend(digits.iter().map(|&d| ALPHABET[d as usize] as char));
+
+    out_string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_to_vec_u8() -> Result<()> {
+        assert_eq!(
+            b"Hello World!".to_vec(),
+            string_to_vec_u8("2NEpo7TZRRrLZSi2U")?
+        );
+        assert_eq!(
+            vec![0, 0, 0x28, 0x7f, 0xb4, 0xcd],
+            string_to_vec_u8("11233QC4")?
+        );
+        assert!(string_to_vec_u8("0OIl").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vec_u8_to_string() {
+        assert_eq!(
+            vec_u8_to_string(b"Hello World!".to_vec()),
+            "2NEpo7TZRRrLZSi2U"
+        );
+        assert_eq!(
+            vec_u8_to_string(vec![0, 0, 0x28, 0x7f, 0xb4, 0xcd]),
+            "11233QC4"
+        );
+        assert_eq!(vec_u8_to_string(Vec::new()), "");
+    }
+}
@@ -1,8 +1,14 @@
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
+use std::borrow::Cow;
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
 
+mod simd;
+mod stream;
+
+pub use stream::{Decoder, Encoder};
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -26,32 +32,162 @@ mod tests {
 
         Ok(())
     }
-}
 
-pub fn string_to_vec_u8(s: &str) -> Result<Vec<u8>> {
-    if s.len() % 4 != 0 {
-        return Err(format!(
-            "Input base64 string length must be a multiple of 4, is: {}",
-            s.len()
-        )
-        .into());
+    #[test]
+    fn test_url_safe_no_pad() -> Result<()> {
+        let data = vec![0xfb, 0xff, 0xbf, 0x3e];
+
+        assert_eq!(vec_u8_to_string(data.clone())?, "+/+/Pg==");
+        assert_eq!(
+            vec_u8_to_string_config(data.clone(), URL_SAFE_NO_PAD)?,
+            "-_-_Pg"
+        );
+        assert_eq!(string_to_vec_u8_config("-_-_Pg", URL_SAFE_NO_PAD)?, data);
+        assert!(string_to_vec_u8_config("-_-_Pg==", URL_SAFE_NO_PAD).is_err());
+        assert!(string_to_vec_u8_config("-_-_P", URL_SAFE_NO_PAD).is_err());
+
+        Ok(())
     }
 
-    let mut out_length = s.len();
-    if s.as_bytes()[out_length - 1] == b'=' {
-        if s.as_bytes()[out_length - 2] == b'=' {
-            out_length -= 1;
+    #[test]
+    fn test_string_to_vec_u8_ct() -> Result<()> {
+        let data: Vec<u8> = (0..=255).collect();
+
+        for &config in &[STANDARD, URL_SAFE_NO_PAD, MIME] {
+            let encoded = vec_u8_to_string_config(data.clone(), config)?;
+            assert_eq!(string_to_vec_u8_config_ct(&encoded, config)?, data);
         }
-        out_length -= 1;
+        assert!(string_to_vec_u8_ct("Qm9u-am9").is_err());
+        assert!(string_to_vec_u8_ct("Qm9uam9\u{100}").is_err());
+        assert!(string_to_vec_u8_ct("Qh==").is_err());
+
+        Ok(())
     }
 
-    let mut as_bytes = Vec::with_capacity(out_length);
+    /// Fastest of many interleaved rounds for each input, so both see the same machine load.
+    fn time_decodes(left: &str, right: &str) -> (std::time::Duration, std::time::Duration) {
+        let time = |input: &str| {
+            let start = std::time::Instant::now();
+            for _ in 0..100 {
+                std::hint::black_box(string_to_vec_u8_ct(std::hint::black_box(input)).unwrap());
+            }
+            start.elapsed()
+        };
 
-    for c in s.chars().take(out_length) {
-        as_bytes.push(b64char_to_u8(c)?);
+        (0..50).fold(
+            (std::time::Duration::MAX, std::time::Duration::MAX),
+            |(l, r), _| (l.min(time(left)), r.min(time(right))),
+        )
+    }
+
+    #[test]
+    #[ignore = "measures wall-clock time, run alone with --ignored"]
+    fn test_string_to_vec_u8_ct_timing() {
+        let upper = "ABCDEFGH".repeat(128);
+        let symbols = "0189+/+/".repeat(128);
+
+        let (t_upper, t_symbols) = time_decodes(&upper, &symbols);
+        let ratio = t_upper.as_secs_f64() / t_symbols.as_secs_f64();
+
+        assert!(
+            (0.8..1.25).contains(&ratio),
+            "Decoding time depends on input characters: {:?} vs {:?}",
+            t_upper,
+            t_symbols
+        );
     }
 
+    #[test]
+    fn test_mime_line_wrap() -> Result<()> {
+        let data = vec![0u8; 60];
+        let encoded = vec_u8_to_string_config(data.clone(), MIME)?;
+        let lines: Vec<&str> = encoded.split("\r\n").collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].len(), 76);
+        assert_eq!(lines[1], "AAAA");
+        assert_eq!(string_to_vec_u8_config(&encoded, MIME)?, data);
+
+        Ok(())
+    }
+}
+
+/// The 64 characters used to encode 6-bit groups.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alphabet {
+    /// RFC 4648 section 4, uses `+` and `/`.
+    Standard,
+    /// RFC 4648 section 5, uses `-` and `_`, safe in URLs and cookies.
+    UrlSafe,
+}
+
+/// Selects the alphabet, padding and line wrapping used to encode and decode.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Config {
+    pub alphabet: Alphabet,
+    /// Emit `=` padding when encoding and require it when decoding.
+    pub padding: bool,
+    /// Wrap encoded output with CRLF every n characters; line breaks are ignored when decoding.
+    pub line_wrap: Option<usize>,
+}
+
+pub const STANDARD: Config = Config {
+    alphabet: Alphabet::Standard,
+    padding: true,
+    line_wrap: None,
+};
+
+pub const STANDARD_NO_PAD: Config = Config {
+    alphabet: Alphabet::Standard,
+    padding: false,
+    line_wrap: None,
+};
+
+pub const URL_SAFE: Config = Config {
+    alphabet: Alphabet::UrlSafe,
+    padding: true,
+    line_wrap: None,
+};
+
+pub const URL_SAFE_NO_PAD: Config = Config {
+    alphabet: Alphabet::UrlSafe,
+    padding: false,
+    line_wrap: None,
+};
+
+/// RFC 2045 transfer encoding: standard alphabet, padded, 76 columns.
+pub const MIME: Config = Config {
+    alphabet: Alphabet::Standard,
+    padding: true,
+    line_wrap: Some(76),
+};
+
+pub fn string_to_vec_u8(s: &str) -> Result<Vec<u8>> {
+    string_to_vec_u8_config(s, STANDARD)
+}
+
+pub fn string_to_vec_u8_config(s: &str, config: Config) -> Result<Vec<u8>> {
+    decode(s, config, cfg!(feature = "simd"))
+}
+
+fn decode(s: &str, config: Config, use_simd: bool) -> Result<Vec<u8>> {
+    let s = significant_chars(s, config);
+    let out_length = unpadded_len(&s, config)?;
     let mut out_vec = Vec::with_capacity((out_length * 6) / 8);
+
+    // The fast path stops before the first block holding an invalid character, always on a
+    // group boundary, and leaves the rest and the error reporting to the scalar loop.
+    let done = match use_simd && s.is_ascii() {
+        true => simd::decode(&s.as_bytes()[..out_length], config.alphabet, &mut out_vec),
+        false => 0,
+    };
+
+    let mut as_bytes = Vec::with_capacity(out_length - done);
+
+    for c in s[done..].chars().take(out_length - done) {
+        as_bytes.push(b64char_to_u8(c, config.alphabet)?);
+    }
+
     for chunk_of_4 in as_bytes.chunks(4) {
         // First byte is the 6 bits of the first char | the 2 first bits of the 2nd char
         out_vec.push((chunk_of_4[0] << 2) | (chunk_of_4[1] >> 4));
@@ -78,65 +214,205 @@ pub fn string_to_vec_u8(s: &str) -> Result<Vec<u8>> {
     Ok(out_vec)
 }
 
-fn b64char_to_u8(c: char) -> Result<u8> {
-    match c {
-        'A'..='Z' => Ok(c as u8 - b'A'),
-        'a'..='z' => Ok(26 + (c as u8 - b'a')),
-        '0'..='9' => Ok(52 + (c as u8 - b'0')),
-        '+' => Ok(62),
-        '/' => Ok(63),
+/// Strips the line breaks a wrapping config allows.
+fn significant_chars(s: &str, config: Config) -> Cow<'_, str> {
+    match config.line_wrap {
+        Some(_) => Cow::Owned(s.chars().filter(|&c| c != '\r' && c != '\n').collect()),
+        None => Cow::Borrowed(s),
+    }
+}
+
+/// Checks the length and padding of the input, returns the number of data characters.
+fn unpadded_len(s: &str, config: Config) -> Result<usize> {
+    let len = s.chars().count();
+    let mut out_length = len;
+    if config.padding {
+        if !len.is_multiple_of(4) {
+            return Err(format!(
+                "Input base64 string length must be a multiple of 4, is: {}",
+                len
+            )
+            .into());
+        }
+
+        if s.ends_with('=') {
+            if s.ends_with("==") {
+                out_length -= 1;
+            }
+            out_length -= 1;
+        }
+    }
+
+    if out_length % 4 == 1 {
+        return Err(format!("Invalid unpadded base64 string length: {}", out_length).into());
+    }
+
+    Ok(out_length)
+}
+
+fn b64char_to_u8(c: char, alphabet: Alphabet) -> Result<u8> {
+    match (c, alphabet) {
+        ('A'..='Z', _) => Ok(c as u8 - b'A'),
+        ('a'..='z', _) => Ok(26 + (c as u8 - b'a')),
+        ('0'..='9', _) => Ok(52 + (c as u8 - b'0')),
+        ('+', Alphabet::Standard) | ('-', Alphabet::UrlSafe) => Ok(62),
+        ('/', Alphabet::Standard) | ('_', Alphabet::UrlSafe) => Ok(63),
         _ => Err(format!("Invalid base64 character {}", c).into()),
     }
 }
 
+pub fn string_to_vec_u8_ct(s: &str) -> Result<Vec<u8>> {
+    string_to_vec_u8_config_ct(s, STANDARD)
+}
+
+/// Same as `string_to_vec_u8_config`, but in time independent of the decoded bytes, for keys
+/// and MACs. Only the length and padding leak; invalid input is reported without details.
+pub fn string_to_vec_u8_config_ct(s: &str, config: Config) -> Result<Vec<u8>> {
+    let s = significant_chars(s, config);
+    let out_length = unpadded_len(&s, config)?;
+
+    let mut invalid = 0u8;
+    let mut as_bytes = Vec::with_capacity(out_length);
+    for c in s.chars().take(out_length) {
+        let (value, valid) = b64char_to_u8_ct(c, config.alphabet);
+        invalid |= !valid;
+        as_bytes.push(value);
+    }
+
+    let mut out_vec = Vec::with_capacity((out_length * 6) / 8);
+    for chunk_of_4 in as_bytes.chunks(4) {
+        out_vec.push((chunk_of_4[0] << 2) | (chunk_of_4[1] >> 4));
+        if chunk_of_4.len() == 2 {
+            invalid |= chunk_of_4[1] << 4;
+            break;
+        }
+
+        out_vec.push((chunk_of_4[1] << 4) | (chunk_of_4[2] >> 2));
+        if chunk_of_4.len() == 3 {
+            invalid |= chunk_of_4[2] << 6;
+            break;
+        }
+
+        out_vec.push((chunk_of_4[2] << 6) | chunk_of_4[3]);
+    }
+
+    if invalid != 0 {
+        return Err("Invalid base64 input.".into());
+    }
+
+    Ok(out_vec)
+}
+
+/// 0xff if `lo <= c <= hi`, 0 otherwise, without branching on `c`.
+fn ct_range_mask(c: i32, lo: u8, hi: u8) -> u8 {
+    (((lo as i32 - 1 - c) & (c - hi as i32 - 1)) >> 8) as u8
+}
+
+/// Returns the 6 bits value and a 0xff mask if `c` was part of the alphabet.
+fn b64char_to_u8_ct(c: char, alphabet: Alphabet) -> (u8, u8) {
+    // Anything above 0xff is clamped to an invalid character
+    let c = std::cmp::min(c as u32, 0x100) as i32;
+    let (c62, c63) = match alphabet {
+        Alphabet::Standard => (b'+', b'/'),
+        Alphabet::UrlSafe => (b'-', b'_'),
+    };
+
+    let upper = ct_range_mask(c, b'A', b'Z');
+    let lower = ct_range_mask(c, b'a', b'z');
+    let digit = ct_range_mask(c, b'0', b'9');
+    let is62 = ct_range_mask(c, c62, c62);
+    let is63 = ct_range_mask(c, c63, c63);
+
+    let c = c as u8;
+    let value = (upper & c.wrapping_sub(b'A'))
+        | (lower & c.wrapping_sub(b'a').wrapping_add(26))
+        | (digit & c.wrapping_sub(b'0').wrapping_add(52))
+        | (is62 & 62)
+        | (is63 & 63);
+
+    (value, upper | lower | digit | is62 | is63)
+}
+
 pub fn vec_u8_to_string(vec: Vec<u8>) -> Result<String> {
-    let mut out_string = String::with_capacity(vec.len() + (4 - vec.len() % 4));
+    vec_u8_to_string_config(vec, STANDARD)
+}
+
+pub fn vec_u8_to_string_config(vec: Vec<u8>, config: Config) -> Result<String> {
+    encode(&vec, config, cfg!(feature = "simd"))
+}
+
+fn encode(vec: &[u8], config: Config, use_simd: bool) -> Result<String> {
+    let mut out_bytes = Vec::with_capacity(vec.len() / 3 * 4 + 4);
+    let alphabet = config.alphabet;
 
-    for chunk_of_3 in vec.chunks(3) {
-        out_string.push(u8_to_b64char(chunk_of_3[0] >> 2)?);
+    let done = match use_simd {
+        true => simd::encode(vec, alphabet, &mut out_bytes),
+        false => 0,
+    };
+
+    // Only alphabet characters were written
+    let mut out_string = String::from_utf8(out_bytes).unwrap();
+
+    for chunk_of_3 in vec[done..].chunks(3) {
+        out_string.push(u8_to_b64char(chunk_of_3[0] >> 2, alphabet)?);
         if chunk_of_3.len() == 1 {
-            out_string.push(u8_to_b64char((chunk_of_3[0] & 0x03) << 4)?);
-            out_string.push('=');
-            out_string.push('=');
+            out_string.push(u8_to_b64char((chunk_of_3[0] & 0x03) << 4, alphabet)?);
+            if config.padding {
+                out_string.push('=');
+                out_string.push('=');
+            }
             break;
         }
 
         out_string.push(u8_to_b64char(
             ((chunk_of_3[0] & 0x03) << 4) | (chunk_of_3[1] >> 4),
+            alphabet,
         )?);
         if chunk_of_3.len() == 2 {
-            out_string.push(u8_to_b64char((chunk_of_3[1] & 0x0f) << 2)?);
-            out_string.push('=');
+            out_string.push(u8_to_b64char((chunk_of_3[1] & 0x0f) << 2, alphabet)?);
+            if config.padding {
+                out_string.push('=');
+            }
             break;
         }
 
         out_string.push(u8_to_b64char(
             (chunk_of_3[1] & 0x0f) << 2 | (chunk_of_3[2] >> 6),
+            alphabet,
         )?);
-        out_string.push(u8_to_b64char(chunk_of_3[2] & 0x3f)?);
+        out_string.push(u8_to_b64char(chunk_of_3[2] & 0x3f, alphabet)?);
     }
 
-    Ok(out_string)
+    match config.line_wrap {
+        Some(0) => Err("Line wrap width must be positive.".into()),
+        Some(width) => Ok(out_string
+            .as_bytes()
+            .chunks(width)
+            .map(|line| std::str::from_utf8(line).unwrap())
+            .collect::<Vec<&str>>()
+            .join("\r\n")),
+        None => Ok(out_string),
+    }
 }
 
-fn u8_to_b64char(b: u8) -> Result<char> {
-    match b {
-        0..=25 => Ok((b + b'A') as char),
-        26..=51 => Ok(((b - 26) + b'a') as char),
-        52..=61 => Ok(((b - 52) + b'0') as char),
-        62 => Ok('+'),
-        63 => Ok('/'),
+fn u8_to_b64char(b: u8, alphabet: Alphabet) -> Result<char> {
+    match (b, alphabet) {
+        (0..=25, _) => Ok((b + b'A') as char),
+        (26..=51, _) => Ok(((b - 26) + b'a') as char),
+        (52..=61, _) => Ok(((b - 52) + b'0') as char),
+        (62, Alphabet::Standard) => Ok('+'),
+        (63, Alphabet::Standard) => Ok('/'),
+        (62, Alphabet::UrlSafe) => Ok('-'),
+        (63, Alphabet::UrlSafe) => Ok('_'),
         _ => Err(format!("Invalid byte {}.", b).into()),
     }
 }
 
 pub fn file_to_vec_u8(path: &str) -> Result<Vec<u8>> {
-    let mut content = String::new();
-    let file = File::open(&path)?;
-    let reader = BufReader::new(file);
-    for line in reader.lines() {
-        content.push_str(line.unwrap().trim());
-    }
+    let file = File::open(path)?;
+    let mut decoder = Decoder::new(BufReader::new(file));
+    let mut out = Vec::new();
+    decoder.read_to_end(&mut out)?;
 
-    return string_to_vec_u8(&content);
+    Ok(out)
 }
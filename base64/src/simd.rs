@@ -0,0 +1,436 @@
+//! SSE2 and AVX2 fast paths, picked at runtime. Both functions only process whole blocks and
+//! return how many input bytes they consumed, the caller finishes with the scalar code.
+//!
+//! Characters are classified with range compares rather than shuffles, which SSE2 lacks. Each
+//! group of 4 characters, or 3 bytes, lives in one 32 bits lane.
+
+use crate::Alphabet;
+
+/// Decodes whole blocks of unpadded base64 characters into `out`, stopping before any
+/// invalid block.
+pub(crate) fn decode(input: &[u8], alphabet: Alphabet, out: &mut Vec<u8>) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return unsafe { x86::decode_avx2(input, alphabet, out) };
+        }
+        if is_x86_feature_detected!("sse2") {
+            return unsafe { x86::decode_sse2(input, alphabet, out) };
+        }
+    }
+
+    let _ = (input, alphabet, out);
+    0
+}
+
+/// Encodes whole blocks of bytes into `out`.
+pub(crate) fn encode(input: &[u8], alphabet: Alphabet, out: &mut Vec<u8>) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return unsafe { x86::encode_avx2(input, alphabet, out) };
+        }
+        if is_x86_feature_detected!("sse2") {
+            return unsafe { x86::encode_sse2(input, alphabet, out) };
+        }
+    }
+
+    let _ = (input, alphabet, out);
+    0
+}
+
+/// Characters for the values 62 and 63.
+fn symbols(alphabet: Alphabet) -> (u8, u8) {
+    match alphabet {
+        Alphabet::Standard => (b'+', b'/'),
+        Alphabet::UrlSafe => (b'-', b'_'),
+    }
+}
+
+/// Offsets added to values above 61 and above 62 when encoding, on top of the `'0' - 52` of
+/// the digits.
+fn symbol_offsets(alphabet: Alphabet) -> (i8, i8) {
+    let (c62, c63) = symbols(alphabet);
+    let to62 = c62 as i8 - 62 - (b'0' as i8 - 52);
+
+    (to62, c63 as i8 - c62 as i8 - 1)
+}
+
+/// Loads 3 bytes per 32 bits lane, as the big endian value of the group.
+fn load_groups<const N: usize>(input: &[u8]) -> [u32; N] {
+    let mut lanes = [0u32; N];
+    for (lane, group) in lanes.iter_mut().zip(input.chunks(3)) {
+        *lane = u32::from_be_bytes([0, group[0], group[1], group[2]]);
+    }
+
+    lanes
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    use super::{load_groups, symbol_offsets, symbols};
+    use crate::Alphabet;
+
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn in_range_sse2(v: __m128i, lo: u8, hi: u8) -> __m128i {
+        // Signed compares, so anything above 0x7f is never in range
+        _mm_and_si128(
+            _mm_cmpgt_epi8(v, _mm_set1_epi8(lo as i8 - 1)),
+            _mm_cmpgt_epi8(_mm_set1_epi8(hi as i8 + 1), v),
+        )
+    }
+
+    /// 6 bits values of 16 characters, and a mask of the valid ones.
+    #[target_feature(enable = "sse2")]
+    unsafe fn values_sse2(v: __m128i, alphabet: Alphabet) -> (__m128i, __m128i) {
+        let (c62, c63) = symbols(alphabet);
+        let upper = in_range_sse2(v, b'A', b'Z');
+        let lower = in_range_sse2(v, b'a', b'z');
+        let digit = in_range_sse2(v, b'0', b'9');
+        let is62 = _mm_cmpeq_epi8(v, _mm_set1_epi8(c62 as i8));
+        let is63 = _mm_cmpeq_epi8(v, _mm_set1_epi8(c63 as i8));
+
+        let value = _mm_or_si128(
+            _mm_or_si128(
+                _mm_and_si128(upper, _mm_sub_epi8(v, _mm_set1_epi8(b'A' as i8))),
+                _mm_and_si128(lower, _mm_sub_epi8(v, _mm_set1_epi8(b'a' as i8 - 26))),
+            ),
+            _mm_or_si128(
+                _mm_and_si128(digit, _mm_add_epi8(v, _mm_set1_epi8(52 - b'0' as i8))),
+                _mm_or_si128(
+                    _mm_and_si128(is62, _mm_set1_epi8(62)),
+                    _mm_and_si128(is63, _mm_set1_epi8(63)),
+                ),
+            ),
+        );
+        let valid = _mm_or_si128(
+            _mm_or_si128(upper, lower),
+            _mm_or_si128(digit, _mm_or_si128(is62, is63)),
+        );
+
+        (value, valid)
+    }
+
+    /// Packs the 4 values of each lane into 3 bytes, in output order.
+    #[target_feature(enable = "sse2")]
+    unsafe fn pack_sse2(v: __m128i) -> __m128i {
+        let six = _mm_set1_epi32(0x3f);
+        let x = _mm_or_si128(
+            _mm_or_si128(
+                _mm_slli_epi32(_mm_and_si128(v, six), 18),
+                _mm_slli_epi32(_mm_and_si128(_mm_srli_epi32(v, 8), six), 12),
+            ),
+            _mm_or_si128(
+                _mm_slli_epi32(_mm_and_si128(_mm_srli_epi32(v, 16), six), 6),
+                _mm_srli_epi32(v, 24),
+            ),
+        );
+
+        // Swap the first and third bytes, the value is big endian
+        let byte = _mm_set1_epi32(0xff);
+        _mm_or_si128(
+            _mm_or_si128(
+                _mm_and_si128(_mm_srli_epi32(x, 16), byte),
+                _mm_and_si128(x, _mm_set1_epi32(0xff00)),
+            ),
+            _mm_slli_epi32(_mm_and_si128(x, byte), 16),
+        )
+    }
+
+    /// Splits the 24 bits of each lane into 4 indexes, one per byte.
+    #[target_feature(enable = "sse2")]
+    unsafe fn unpack_sse2(x: __m128i) -> __m128i {
+        let six = _mm_set1_epi32(0x3f);
+        _mm_or_si128(
+            _mm_or_si128(
+                _mm_and_si128(_mm_srli_epi32(x, 18), six),
+                _mm_slli_epi32(_mm_and_si128(_mm_srli_epi32(x, 12), six), 8),
+            ),
+            _mm_or_si128(
+                _mm_slli_epi32(_mm_and_si128(_mm_srli_epi32(x, 6), six), 16),
+                _mm_slli_epi32(_mm_and_si128(x, six), 24),
+            ),
+        )
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn ascii_sse2(i: __m128i, alphabet: Alphabet) -> __m128i {
+        let (to62, to63) = symbol_offsets(alphabet);
+        let step = |above: i8, offset: i8| {
+            _mm_and_si128(
+                _mm_cmpgt_epi8(i, _mm_set1_epi8(above)),
+                _mm_set1_epi8(offset),
+            )
+        };
+
+        let offset = _mm_add_epi8(
+            _mm_add_epi8(_mm_set1_epi8(b'A' as i8), step(25, 6)),
+            _mm_add_epi8(
+                step(51, b'0' as i8 - 52 - (b'a' as i8 - 26)),
+                _mm_add_epi8(step(61, to62), step(62, to63)),
+            ),
+        );
+
+        _mm_add_epi8(i, offset)
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn decode_sse2(input: &[u8], alphabet: Alphabet, out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 16];
+
+        while i + 16 <= input.len() {
+            let v = _mm_loadu_si128(input.as_ptr().add(i) as *const __m128i);
+            let (values, valid) = values_sse2(v, alphabet);
+
+            if _mm_movemask_epi8(valid) != 0xffff {
+                break;
+            }
+
+            _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, pack_sse2(values));
+            for lane in block.chunks(4) {
+                out.extend_from_slice(&lane[..3]);
+            }
+            i += 16;
+        }
+
+        i
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn encode_sse2(input: &[u8], alphabet: Alphabet, out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 16];
+
+        while i + 12 <= input.len() {
+            let lanes = load_groups::<4>(&input[i..i + 12]);
+            let x = _mm_loadu_si128(lanes.as_ptr() as *const __m128i);
+
+            let chars = ascii_sse2(unpack_sse2(x), alphabet);
+            _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, chars);
+            out.extend_from_slice(&block);
+            i += 12;
+        }
+
+        i
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn in_range_avx2(v: __m256i, lo: u8, hi: u8) -> __m256i {
+        _mm256_and_si256(
+            _mm256_cmpgt_epi8(v, _mm256_set1_epi8(lo as i8 - 1)),
+            _mm256_cmpgt_epi8(_mm256_set1_epi8(hi as i8 + 1), v),
+        )
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn values_avx2(v: __m256i, alphabet: Alphabet) -> (__m256i, __m256i) {
+        let (c62, c63) = symbols(alphabet);
+        let upper = in_range_avx2(v, b'A', b'Z');
+        let lower = in_range_avx2(v, b'a', b'z');
+        let digit = in_range_avx2(v, b'0', b'9');
+        let is62 = _mm256_cmpeq_epi8(v, _mm256_set1_epi8(c62 as i8));
+        let is63 = _mm256_cmpeq_epi8(v, _mm256_set1_epi8(c63 as i8));
+
+        let value = _mm256_or_si256(
+            _mm256_or_si256(
+                _mm256_and_si256(upper, _mm256_sub_epi8(v, _mm256_set1_epi8(b'A' as i8))),
+                _mm256_and_si256(lower, _mm256_sub_epi8(v, _mm256_set1_epi8(b'a' as i8 - 26))),
+            ),
+            _mm256_or_si256(
+                _mm256_and_si256(digit, _mm256_add_epi8(v, _mm256_set1_epi8(52 - b'0' as i8))),
+                _mm256_or_si256(
+                    _mm256_and_si256(is62, _mm256_set1_epi8(62)),
+                    _mm256_and_si256(is63, _mm256_set1_epi8(63)),
+                ),
+            ),
+        );
+        let valid = _mm256_or_si256(
+            _mm256_or_si256(upper, lower),
+            _mm256_or_si256(digit, _mm256_or_si256(is62, is63)),
+        );
+
+        (value, valid)
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn pack_avx2(v: __m256i) -> __m256i {
+        let six = _mm256_set1_epi32(0x3f);
+        let x = _mm256_or_si256(
+            _mm256_or_si256(
+                _mm256_slli_epi32(_mm256_and_si256(v, six), 18),
+                _mm256_slli_epi32(_mm256_and_si256(_mm256_srli_epi32(v, 8), six), 12),
+            ),
+            _mm256_or_si256(
+                _mm256_slli_epi32(_mm256_and_si256(_mm256_srli_epi32(v, 16), six), 6),
+                _mm256_srli_epi32(v, 24),
+            ),
+        );
+
+        let byte = _mm256_set1_epi32(0xff);
+        _mm256_or_si256(
+            _mm256_or_si256(
+                _mm256_and_si256(_mm256_srli_epi32(x, 16), byte),
+                _mm256_and_si256(x, _mm256_set1_epi32(0xff00)),
+            ),
+            _mm256_slli_epi32(_mm256_and_si256(x, byte), 16),
+        )
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn unpack_avx2(x: __m256i) -> __m256i {
+        let six = _mm256_set1_epi32(0x3f);
+        _mm256_or_si256(
+            _mm256_or_si256(
+                _mm256_and_si256(_mm256_srli_epi32(x, 18), six),
+                _mm256_slli_epi32(_mm256_and_si256(_mm256_srli_epi32(x, 12), six), 8),
+            ),
+            _mm256_or_si256(
+                _mm256_slli_epi32(_mm256_and_si256(_mm256_srli_epi32(x, 6), six), 16),
+                _mm256_slli_epi32(_mm256_and_si256(x, six), 24),
+            ),
+        )
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn ascii_avx2(i: __m256i, alphabet: Alphabet) -> __m256i {
+        let (to62, to63) = symbol_offsets(alphabet);
+        let step = |above: i8, offset: i8| {
+            _mm256_and_si256(
+                _mm256_cmpgt_epi8(i, _mm256_set1_epi8(above)),
+                _mm256_set1_epi8(offset),
+            )
+        };
+
+        let offset = _mm256_add_epi8(
+            _mm256_add_epi8(_mm256_set1_epi8(b'A' as i8), step(25, 6)),
+            _mm256_add_epi8(
+                step(51, b'0' as i8 - 52 - (b'a' as i8 - 26)),
+                _mm256_add_epi8(step(61, to62), step(62, to63)),
+            ),
+        );
+
+        _mm256_add_epi8(i, offset)
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn decode_avx2(input: &[u8], alphabet: Alphabet, out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 32];
+
+        while i + 32 <= input.len() {
+            let v = _mm256_loadu_si256(input.as_ptr().add(i) as *const __m256i);
+            let (values, valid) = values_avx2(v, alphabet);
+
+            if _mm256_movemask_epi8(valid) != -1 {
+                break;
+            }
+
+            _mm256_storeu_si256(block.as_mut_ptr() as *mut __m256i, pack_avx2(values));
+            for lane in block.chunks(4) {
+                out.extend_from_slice(&lane[..3]);
+            }
+            i += 32;
+        }
+
+        i
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn encode_avx2(input: &[u8], alphabet: Alphabet, out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 32];
+
+        while i + 24 <= input.len() {
+            let lanes = load_groups::<8>(&input[i..i + 24]);
+            let x = _mm256_loadu_si256(lanes.as_ptr() as *const __m256i);
+
+            let chars = ascii_avx2(unpack_avx2(x), alphabet);
+            _mm256_storeu_si256(block.as_mut_ptr() as *mut __m256i, chars);
+            out.extend_from_slice(&block);
+            i += 24;
+        }
+
+        i
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{STANDARD, URL_SAFE_NO_PAD};
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 167 + 13) as u8).collect()
+    }
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        for &config in &[STANDARD, URL_SAFE_NO_PAD] {
+            for len in 0..100 {
+                let data = sample(len);
+                let scalar = crate::encode(&data, config, false).unwrap();
+
+                assert_eq!(crate::encode(&data, config, true).unwrap(), scalar);
+                assert_eq!(crate::decode(&scalar, config, true).unwrap(), data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_simd_rejects_invalid() {
+        let valid = crate::encode(&sample(96), STANDARD, false).unwrap();
+
+        // Padding in the last two positions may well be valid
+        for i in 0..valid.len() - 2 {
+            for bad in ["-", "_", "=", "@", "[", "`", "{", ":", "*", " "] {
+                let mut invalid = valid.clone();
+                invalid.replace_range(i..i + 1, bad);
+                assert!(
+                    crate::decode(&invalid, STANDARD, true).is_err(),
+                    "{}",
+                    invalid
+                );
+            }
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[test]
+    fn test_each_instruction_set() {
+        let data = sample(100);
+
+        for &alphabet in &[Alphabet::Standard, Alphabet::UrlSafe] {
+            let config = crate::Config {
+                alphabet,
+                ..STANDARD
+            };
+            let encoded = crate::encode(&data, config, false).unwrap();
+
+            if is_x86_feature_detected!("sse2") {
+                let (mut chars, mut bytes) = (Vec::new(), Vec::new());
+                let done = unsafe { x86::encode_sse2(&data, alphabet, &mut chars) };
+                assert_eq!(done, 96);
+                assert_eq!(&chars[..], &encoded.as_bytes()[..128]);
+                let done = unsafe { x86::decode_sse2(encoded.as_bytes(), alphabet, &mut bytes) };
+                assert_eq!(done, 128);
+                assert_eq!(&bytes[..], &data[..96]);
+            }
+
+            if is_x86_feature_detected!("avx2") {
+                let (mut chars, mut bytes) = (Vec::new(), Vec::new());
+                let done = unsafe { x86::encode_avx2(&data, alphabet, &mut chars) };
+                assert_eq!(done, 96);
+                assert_eq!(&chars[..], &encoded.as_bytes()[..128]);
+                let done = unsafe { x86::decode_avx2(encoded.as_bytes(), alphabet, &mut bytes) };
+                assert_eq!(done, 128);
+                assert_eq!(&bytes[..], &data[..96]);
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use crate::{string_to_vec_u8_config, vec_u8_to_string_config, Config, STANDARD};
+use std::io::{self, Read, Write};
+
+fn invalid_data<E>(e: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// Decodes base64 read from `R`, ignoring any whitespace and line breaks.
+pub struct Decoder<R: Read> {
+    inner: R,
+    config: Config,
+    // Significant characters read but not decoded yet
+    pending: Vec<u8>,
+    // Decoded bytes not handed out yet
+    decoded: Vec<u8>,
+    pos: usize,
+    padded: bool,
+    eof: bool,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(inner: R) -> Self {
+        Decoder::with_config(inner, STANDARD)
+    }
+
+    pub fn with_config(inner: R, config: Config) -> Self {
+        Decoder {
+            inner,
+            config: Config {
+                line_wrap: None,
+                ..config
+            },
+            pending: Vec::new(),
+            decoded: Vec::new(),
+            pos: 0,
+            padded: false,
+            eof: false,
+        }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn decode(&self, group: &[u8]) -> io::Result<Vec<u8>> {
+        // Only ASCII is ever pushed to pending
+        let s = std::str::from_utf8(group).map_err(invalid_data)?;
+        string_to_vec_u8_config(s, self.config).map_err(invalid_data)
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 1024];
+        let n = self.inner.read(&mut chunk)?;
+
+        self.decoded.clear();
+        self.pos = 0;
+
+        if n == 0 {
+            // Whatever is left is the final, possibly unpadded, group
+            self.eof = true;
+            let group = std::mem::take(&mut self.pending);
+            if !group.is_empty() {
+                self.decoded = self.decode(&group)?;
+            }
+            return Ok(());
+        }
+
+        for &c in &chunk[..n] {
+            if c.is_ascii_whitespace() {
+                continue;
+            }
+            if !c.is_ascii() {
+                return Err(invalid_data(format!("Invalid base64 byte {}", c)));
+            }
+            if self.padded && c != b'=' {
+                return Err(invalid_data("Data found after base64 padding."));
+            }
+            if c == b'=' {
+                self.padded = true;
+            }
+            self.pending.push(c);
+        }
+
+        let ready = self.pending.len() / 4 * 4;
+        if ready > 0 {
+            let group: Vec<u8> = self.pending.drain(..ready).collect();
+            self.decoded = self.decode(&group)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.decoded.len() {
+            if self.eof {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let n = std::cmp::min(buf.len(), self.decoded.len() - self.pos);
+        buf[..n].copy_from_slice(&self.decoded[self.pos..self.pos + n]);
+        self.pos += n;
+
+        Ok(n)
+    }
+}
+
+/// Encodes everything written to it as base64 into `W`.
+///
+/// The final partial group is only written by `finish`, or on drop.
+pub struct Encoder<W: Write> {
+    inner: Option<W>,
+    config: Config,
+    // Less than 3 bytes waiting for a full group
+    leftover: Vec<u8>,
+    column: usize,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(inner: W) -> Self {
+        Encoder::with_config(inner, STANDARD)
+    }
+
+    pub fn with_config(inner: W, config: Config) -> Self {
+        Encoder {
+            inner: Some(inner),
+            config,
+            leftover: Vec::with_capacity(3),
+            column: 0,
+        }
+    }
+
+    /// Writes the final group and its padding, and gives back the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.write_final()?;
+
+        Ok(self.inner.take().unwrap())
+    }
+
+    fn write_final(&mut self) -> io::Result<()> {
+        let last = std::mem::take(&mut self.leftover);
+        self.write_encoded(last)?;
+        self.inner.as_mut().unwrap().flush()
+    }
+
+    fn write_encoded(&mut self, data: Vec<u8>) -> io::Result<()> {
+        let config = Config {
+            line_wrap: None,
+            ..self.config
+        };
+        let encoded = vec_u8_to_string_config(data, config).map_err(invalid_data)?;
+        let mut encoded = encoded.as_bytes();
+        let inner = self.inner.as_mut().unwrap();
+
+        let width = match self.config.line_wrap {
+            Some(0) => return Err(invalid_data("Line wrap width must be positive.")),
+            Some(width) => width,
+            None => return inner.write_all(encoded),
+        };
+
+        while !encoded.is_empty() {
+            if self.column == width {
+                inner.write_all(b"\r\n")?;
+                self.column = 0;
+            }
+            let take = std::cmp::min(width - self.column, encoded.len());
+            inner.write_all(&encoded[..take])?;
+            self.column += take;
+            encoded = &encoded[take..];
+        }
+
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.leftover.extend_from_slice(buf);
+
+        let full = self.leftover.len() / 3 * 3;
+        let groups: Vec<u8> = self.leftover.drain(..full).collect();
+        self.write_encoded(groups)?;
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.as_mut().unwrap().flush()
+    }
+}
+
+impl<W: Write> Drop for Encoder<W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.write_final();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{vec_u8_to_string_config, MIME, URL_SAFE_NO_PAD};
+
+    /// Reader handing out at most one byte per call, to exercise chunk boundaries.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_decoder_line_breaks() -> io::Result<()> {
+        let mut out = Vec::new();
+        Decoder::new(Trickle(b"Qm9u\r\nam91\ncgo=\n")).read_to_end(&mut out)?;
+        assert_eq!(out, b"Bonjour\n");
+
+        let mut out = Vec::new();
+        Decoder::with_config(Trickle(b"-_-_\nPg"), URL_SAFE_NO_PAD).read_to_end(&mut out)?;
+        assert_eq!(out, vec![0xfb, 0xff, 0xbf, 0x3e]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decoder_rejects_data_after_padding() {
+        let mut out = Vec::new();
+        assert!(Decoder::new(Trickle(b"Qg==Qg=="))
+            .read_to_end(&mut out)
+            .is_err());
+    }
+
+    #[test]
+    fn test_encoder_matches_config() -> io::Result<()> {
+        let data: Vec<u8> = (0..=255).collect();
+
+        let mut encoder = Encoder::with_config(Vec::new(), MIME);
+        for chunk in data.chunks(7) {
+            encoder.write_all(chunk)?;
+        }
+        let encoded = encoder.finish()?;
+
+        assert_eq!(
+            String::from_utf8(encoded).unwrap(),
+            vec_u8_to_string_config(data, MIME).unwrap()
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+/// Ascii85 as used by Adobe and btoa: `!` to `u`, with `z` for a zero group.
+/// The `<~` and `~>` delimiters are accepted but not required when decoding.
+pub fn string_to_vec_u8(s: &str) -> Result<Vec<u8>> {
+    let s = s.trim();
+    let s = s.strip_prefix("<~").unwrap_or(s);
+    let s = s.strip_suffix("~>").unwrap_or(s);
+
+    let mut out_vec = Vec::with_capacity(s.len() * 4 / 5);
+    let mut group = Vec::with_capacity(5);
+
+    for c in s.chars().filter(|c| !c.is_ascii_whitespace()) {
+        match c {
+            'z' if group.is_empty() => out_vec.extend_from_slice(&[0; 4]),
+            '!'..='u' => {
+                group.push(c as u8 - b'!');
+                if group.len() == 5 {
+                    out_vec.extend_from_slice(&decode_group(&group)?);
+                    group.clear();
+                }
+            }
+            _ => return Err(format!("Invalid base85 character {}", c).into()),
+        }
+    }
+
+    match group.len() {
+        0 => {}
+        1 => return Err("Final base85 group needs at least 2 characters.".into()),
+        n => {
+            // Complete with the highest digit, then drop the bytes it made up
+            group.resize(5, 84);
+            out_vec.extend_from_slice(&decode_group(&group)?[..n - 1]);
+        }
+    }
+
+    Ok(out_vec)
+}
+
+fn decode_group(group: &[u8]) -> Result<[u8; 4]> {
+    let value = group.iter().fold(0u64, |acc, &d| acc * 85 + d as u64);
+
+    if value > u32::MAX as u64 {
+        return Err("Base85 group overflows 32 bits.".into());
+    }
+
+    Ok((value as u32).to_be_bytes())
+}
+
+pub fn vec_u8_to_string(vec: Vec<u8>) -> String {
+    let mut out_string = String::with_capacity(vec.len().div_ceil(4) * 5);
+
+    for chunk in vec.chunks(4) {
+        let mut block = [0u8; 4];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let mut value = u32::from_be_bytes(block);
+
+        if chunk.len() == 4 && value == 0 {
+            out_string.push('z');
+            continue;
+        }
+
+        let mut digits = [0u8; 5];
+        for d in digits.iter_mut().rev() {
+            *d = (value % 85) as u8 + b'!';
+            value /= 85;
+        }
+
+        // A partial group of n bytes only needs n + 1 characters
+        for &d in &digits[..chunk.len() + 1] {
+            out_string.push(d as char);
+        }
+    }
+
+    out_string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string_to_vec_u8() -> Result<()> {
+        assert_eq!(b"Man is".to_vec(), string_to_vec_u8("9jqo^Bla")?);
+        assert_eq!(b"Man is".to_vec(), string_to_vec_u8("<~9jqo^\nBla~>")?);
+        assert_eq!(vec![0, 0, 0, 0, 1], string_to_vec_u8("z!<")?);
+        assert!(string_to_vec_u8("9jqo^B").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_vec_u8_to_string() {
+        assert_eq!(vec_u8_to_string(b"Man is".to_vec()), "9jqo^Bla");
+        assert_eq!(vec_u8_to_string(vec![0, 0, 0, 0, 1]), "z!<");
+    }
+}
@@ -1,4 +1,4 @@
-use oracle::Oracle;
+use oracle::{AesOracle, DecryptionOracle, EncryptionOracle, OracleRng, VerificationOracle};
 use rand::Rng;
 use std::borrow::Cow;
 use std::fmt;
@@ -16,6 +16,27 @@ mod tests {
 
         assert_eq!(profile.email, "aroleadmin@a.com");
     }
+
+    #[test]
+    fn test_comment_quotes_special_chars() -> Result<()> {
+        let oracle = CommentOracle::with_seed(16)?;
+        let cipher = oracle.encrypt(b";admin=true;")?;
+
+        assert!(!oracle.is_admin(&cipher)?);
+        assert!(oracle
+            .decrypt_comment(&cipher)?
+            .windows(18)
+            .any(|w| w == b"%3Badmin%3Dtrue%3B"));
+
+        // Bytes that are not UTF-8 go through unchanged
+        let cipher = oracle.encrypt(b"\xff;\xfe")?;
+        assert!(oracle
+            .decrypt_comment(&cipher)?
+            .windows(5)
+            .any(|w| w == b"\xff%3B\xfe"));
+
+        Ok(())
+    }
 }
 
 #[derive(PartialEq)]
@@ -90,12 +111,21 @@ impl Profile {
     }
 }
 
+/// Builds the user profile of challenge 13, dropping the `&` and `=` that could inject fields.
+pub fn profile_for(email: &str) -> Profile {
+    Profile {
+        email: email.replace(&['&', '='][..], ""),
+        uid: 10,
+        role: Role::User,
+    }
+}
+
 pub struct ProfileOracle {
     key: [u8; 16],
     uid: u32,
 }
 
-impl Oracle for ProfileOracle {
+impl EncryptionOracle for ProfileOracle {
     fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
         /* let clean_email = data
             .iter()
@@ -118,12 +148,25 @@ impl Oracle for ProfileOracle {
     }
 }
 
+impl VerificationOracle for ProfileOracle {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        Ok(self.profile_from_encrypted(cipher)?.is_admin())
+    }
+}
+
 impl ProfileOracle {
-    pub fn new() -> ProfileOracle {
-        let mut rng = rand::thread_rng();
+    pub fn new() -> Result<ProfileOracle> {
+        Ok(Self::from_rng(oracle::rng()?))
+    }
+
+    pub fn with_seed(seed: u64) -> ProfileOracle {
+        Self::from_rng(oracle::seeded_rng(seed))
+    }
+
+    fn from_rng(mut rng: OracleRng) -> ProfileOracle {
         let mut key = [0u8; 16];
-        for i in 0..16 {
-            key[i] = rng.gen::<u8>();
+        for k in key.iter_mut() {
+            *k = rng.gen::<u8>();
         }
 
         let uid = rng.gen::<u32>();
@@ -135,7 +178,7 @@ impl ProfileOracle {
         aes::encrypt_aes_128_ecb(profile.encode()?.as_bytes(), &self.key)
     }
 
-    pub fn profile_from_encrypted(&self, enc: &Vec<u8>) -> Result<Profile> {
+    pub fn profile_from_encrypted(&self, enc: &[u8]) -> Result<Profile> {
         let dec = aes::decrypt_aes_128_ecb(enc, &self.key)?;
 
         Profile::from_encoded(&String::from_utf8(dec)?)
@@ -151,3 +194,82 @@ impl ProfileOracle {
         })
     }
 }
+
+const COMMENT_PREFIX: &str = "comment1=cooking%20MCs;userdata=";
+const COMMENT_SUFFIX: &str = ";comment2=%20like%20a%20pound%20of%20bacon";
+
+/// Oracle of challenge 16: CBC encrypts the attacker input, with `;` and `=` quoted out, as the
+/// `userdata` field of a comment string. The CTR flavour is the oracle of challenge 26.
+pub struct CommentOracle {
+    aes_oracle: AesOracle,
+}
+
+impl CommentOracle {
+    pub fn new() -> Result<Self> {
+        Self::from_rng(oracle::rng()?, aes::MODE::CBC)
+    }
+
+    pub fn with_seed(seed: u64) -> Result<Self> {
+        Self::from_rng(oracle::seeded_rng(seed), aes::MODE::CBC)
+    }
+
+    pub fn ctr() -> Result<Self> {
+        Self::from_rng(oracle::rng()?, aes::MODE::CTR)
+    }
+
+    pub fn ctr_with_seed(seed: u64) -> Result<Self> {
+        Self::from_rng(oracle::seeded_rng(seed), aes::MODE::CTR)
+    }
+
+    fn from_rng(rng: OracleRng, mode: aes::MODE) -> Result<Self> {
+        let aes_oracle = AesOracle::builder()
+            .rng(rng)
+            .mode(mode)
+            .prefix(COMMENT_PREFIX.as_bytes().to_vec())
+            .suffix(COMMENT_SUFFIX.as_bytes().to_vec())
+            .filter(|data| {
+                let mut quoted = Vec::with_capacity(data.len());
+                for &b in data {
+                    match b {
+                        b';' => quoted.extend_from_slice(b"%3B"),
+                        b'=' => quoted.extend_from_slice(b"%3D"),
+                        _ => quoted.push(b),
+                    }
+                }
+                Ok(quoted)
+            })
+            .build()?;
+
+        Ok(CommentOracle { aes_oracle })
+    }
+
+    /// Decrypts and unpads a comment string, blocks tampered with may not be valid UTF-8.
+    pub fn decrypt_comment(&self, cipher: &[u8]) -> Result<Vec<u8>> {
+        let mut plaintext = self.aes_oracle.decrypt(cipher)?;
+        if self.aes_oracle.mode != aes::MODE::CTR {
+            aes::unpad_pkcs7(&mut plaintext, 16)?;
+        }
+
+        Ok(plaintext)
+    }
+
+    /// Whether the decrypted comment string holds an `admin=true` field.
+    pub fn is_admin(&self, cipher: &[u8]) -> Result<bool> {
+        Ok(self
+            .decrypt_comment(cipher)?
+            .split(|&c| c == b';')
+            .any(|field| field == b"admin=true"))
+    }
+}
+
+impl EncryptionOracle for CommentOracle {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.aes_oracle.encrypt(data)
+    }
+}
+
+impl VerificationOracle for CommentOracle {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        self.is_admin(cipher)
+    }
+}
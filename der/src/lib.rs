@@ -0,0 +1,396 @@
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+pub const INTEGER: u8 = 0x02;
+pub const BIT_STRING: u8 = 0x03;
+pub const OCTET_STRING: u8 = 0x04;
+pub const NULL: u8 = 0x05;
+pub const OBJECT_IDENTIFIER: u8 = 0x06;
+pub const SEQUENCE: u8 = 0x30;
+pub const SET: u8 = 0x31;
+
+/// Reads consecutive TLVs out of a DER buffer.
+pub struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Reader { data }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    pub fn peek_tag(&self) -> Option<u8> {
+        self.data.first().cloned()
+    }
+
+    /// Reads the next element, returning its tag and value.
+    pub fn read_tlv(&mut self) -> Result<(u8, &'a [u8])> {
+        if self.data.len() < 2 {
+            return Err("Truncated DER element".into());
+        }
+
+        let tag = self.data[0];
+        if tag & 0x1f == 0x1f {
+            return Err(format!("Multi-byte DER tags are not supported: {:#x}", tag).into());
+        }
+
+        let (len, header_len) = match self.data[1] {
+            l @ 0..=0x7f => (l as usize, 2),
+            0x80 => return Err("Indefinite length is not allowed in DER".into()),
+            l => {
+                let n = (l & 0x7f) as usize;
+                if n > 4 || self.data.len() < 2 + n {
+                    return Err(format!("Invalid DER length of {} bytes", n).into());
+                }
+                let len = self.data[2..2 + n]
+                    .iter()
+                    .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+                if self.data[2] == 0 || len < 0x80 {
+                    return Err("Non minimal DER length".into());
+                }
+                (len, 2 + n)
+            }
+        };
+
+        if self.data.len() < header_len + len {
+            return Err(format!(
+                "DER element of length {} overflows its {} bytes buffer",
+                len,
+                self.data.len() - header_len
+            )
+            .into());
+        }
+
+        let value = &self.data[header_len..header_len + len];
+        self.data = &self.data[header_len + len..];
+
+        Ok((tag, value))
+    }
+
+    /// Reads the next element if it has the expected tag, leaving the reader untouched otherwise.
+    pub fn read_expected(&mut self, expected: u8) -> Result<&'a [u8]> {
+        match self.peek_tag() {
+            Some(tag) if tag == expected => Ok(self.read_tlv()?.1),
+            Some(tag) => Err(format!("Expected DER tag {:#x}, found {:#x}", expected, tag).into()),
+            None => Err("Truncated DER element".into()),
+        }
+    }
+
+    pub fn read_sequence(&mut self) -> Result<Reader<'a>> {
+        Ok(Reader::new(self.read_expected(SEQUENCE)?))
+    }
+
+    pub fn read_set(&mut self) -> Result<Reader<'a>> {
+        Ok(Reader::new(self.read_expected(SET)?))
+    }
+
+    /// Reads a non negative INTEGER as its big endian magnitude, without leading zeros.
+    pub fn read_integer(&mut self) -> Result<Vec<u8>> {
+        let value = self.read_expected(INTEGER)?;
+
+        match value {
+            [] => Err("Empty DER integer".into()),
+            [b, ..] if b & 0x80 != 0 => Err("Negative DER integers are not supported".into()),
+            [0, b, ..] if b & 0x80 == 0 => Err("Non minimal DER integer".into()),
+            [0, rest @ ..] if !rest.is_empty() => Ok(rest.to_vec()),
+            _ => Ok(value.to_vec()),
+        }
+    }
+
+    pub fn read_octet_string(&mut self) -> Result<&'a [u8]> {
+        self.read_expected(OCTET_STRING)
+    }
+
+    /// Reads a BIT STRING, which must have no unused bits.
+    pub fn read_bit_string(&mut self) -> Result<&'a [u8]> {
+        match self.read_expected(BIT_STRING)? {
+            [0, rest @ ..] => Ok(rest),
+            _ => Err("Only byte aligned DER bit strings are supported".into()),
+        }
+    }
+
+    pub fn read_null(&mut self) -> Result<()> {
+        match self.read_expected(NULL)? {
+            [] => Ok(()),
+            _ => Err("DER NULL must be empty".into()),
+        }
+    }
+
+    pub fn read_oid(&mut self) -> Result<Vec<u64>> {
+        let value = self.read_expected(OBJECT_IDENTIFIER)?;
+        if value.is_empty() {
+            return Err("Empty DER object identifier".into());
+        }
+
+        let mut arcs = Vec::new();
+        let mut current = 0u64;
+        let mut arc_start = true;
+
+        for (i, &b) in value.iter().enumerate() {
+            if arc_start && b == 0x80 {
+                return Err("Non minimal DER object identifier arc".into());
+            }
+            if current > u64::MAX >> 7 {
+                return Err("DER object identifier arc does not fit in 64 bits".into());
+            }
+            current = (current << 7) | (b & 0x7f) as u64;
+            arc_start = b & 0x80 == 0;
+            if b & 0x80 != 0 {
+                if i == value.len() - 1 {
+                    return Err("Truncated DER object identifier".into());
+                }
+                continue;
+            }
+
+            if arcs.is_empty() {
+                // First byte packs the two first arcs as 40 * x + y
+                let first = std::cmp::min(current / 40, 2);
+                arcs.push(first);
+                arcs.push(current - first * 40);
+            } else {
+                arcs.push(current);
+            }
+            current = 0;
+        }
+
+        Ok(arcs)
+    }
+}
+
+/// Builds a DER buffer element by element.
+#[derive(Default)]
+pub struct Writer {
+    out: Vec<u8>,
+}
+
+impl Writer {
+    pub fn new() -> Self {
+        Writer { out: Vec::new() }
+    }
+
+    pub fn into_vec(self) -> Vec<u8> {
+        self.out
+    }
+
+    pub fn write_tlv(&mut self, tag: u8, value: &[u8]) {
+        self.out.push(tag);
+
+        let len = value.len();
+        if len < 0x80 {
+            self.out.push(len as u8);
+        } else {
+            let bytes = len.to_be_bytes();
+            let skip = bytes.iter().take_while(|&&b| b == 0).count();
+            self.out.push(0x80 | (bytes.len() - skip) as u8);
+            self.out.extend_from_slice(&bytes[skip..]);
+        }
+
+        self.out.extend_from_slice(value);
+    }
+
+    /// Writes the children built by `f` inside a SEQUENCE.
+    pub fn write_sequence<F: FnOnce(&mut Writer) -> Result<()>>(&mut self, f: F) -> Result<()> {
+        let mut inner = Writer::new();
+        f(&mut inner)?;
+        self.write_tlv(SEQUENCE, &inner.out);
+
+        Ok(())
+    }
+
+    pub fn write_set<F: FnOnce(&mut Writer) -> Result<()>>(&mut self, f: F) -> Result<()> {
+        let mut inner = Writer::new();
+        f(&mut inner)?;
+        self.write_tlv(SET, &inner.out);
+
+        Ok(())
+    }
+
+    /// Writes a non negative INTEGER from its big endian magnitude.
+    pub fn write_integer(&mut self, magnitude: &[u8]) {
+        let skip = magnitude.iter().take_while(|&&b| b == 0).count();
+        let magnitude = &magnitude[skip..];
+
+        let mut value = Vec::with_capacity(magnitude.len() + 1);
+        if magnitude.is_empty() || magnitude[0] & 0x80 != 0 {
+            value.push(0);
+        }
+        value.extend_from_slice(magnitude);
+
+        self.write_tlv(INTEGER, &value);
+    }
+
+    pub fn write_octet_string(&mut self, value: &[u8]) {
+        self.write_tlv(OCTET_STRING, value);
+    }
+
+    pub fn write_bit_string(&mut self, value: &[u8]) {
+        let mut content = vec![0];
+        content.extend_from_slice(value);
+        self.write_tlv(BIT_STRING, &content);
+    }
+
+    pub fn write_null(&mut self) {
+        self.write_tlv(NULL, &[]);
+    }
+
+    /// Writes an OBJECT IDENTIFIER. The first arc is 0, 1 or 2, and the second one stays under 40
+    /// unless the first is 2, as both are packed into the first subidentifier.
+    pub fn write_oid(&mut self, arcs: &[u64]) -> Result<()> {
+        match arcs {
+            [first, second, ..] if *first < 2 && *second < 40 => (),
+            [2, second, ..] if *second <= u64::MAX - 80 => (),
+            _ => return Err(format!("Invalid OBJECT IDENTIFIER arcs {:?}", arcs).into()),
+        }
+
+        let mut value = Vec::new();
+        let mut push_arc = |mut arc: u64| {
+            let mut base128 = vec![(arc & 0x7f) as u8];
+            arc >>= 7;
+            while arc > 0 {
+                base128.push(0x80 | (arc & 0x7f) as u8);
+                arc >>= 7;
+            }
+            value.extend(base128.iter().rev());
+        };
+
+        push_arc(arcs[0] * 40 + arcs[1]);
+        for &arc in arcs.iter().skip(2) {
+            push_arc(arc);
+        }
+
+        self.write_tlv(OBJECT_IDENTIFIER, &value);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RSAPublicKey ::= SEQUENCE { modulus INTEGER, publicExponent INTEGER }
+    const RSA_PUBLIC_KEY: [u8; 74] = [
+        0x30, 0x48, 0x02, 0x41, 0x00, 0xc0, 0x0a, 0x09, 0xa0, 0x9d, 0x70, 0x72, 0x29, 0xa1, 0xa0,
+        0xf7, 0x97, 0x2a, 0xb2, 0xf6, 0x03, 0xa7, 0x51, 0x08, 0xc5, 0xb9, 0xdf, 0x99, 0x6c, 0x0f,
+        0xf3, 0xb0, 0x18, 0x17, 0x44, 0xa0, 0xe4, 0x38, 0x9d, 0xfa, 0x15, 0x74, 0xa5, 0x69, 0xd9,
+        0x4a, 0x66, 0x02, 0x8e, 0x8c, 0x7d, 0x38, 0xcc, 0xa2, 0x8e, 0xb0, 0xb1, 0x40, 0x83, 0xda,
+        0xf1, 0xd1, 0x33, 0x08, 0x21, 0x58, 0x0a, 0xeb, 0x0f, 0x02, 0x03, 0x01, 0x00, 0x01,
+    ];
+
+    #[test]
+    fn test_read_rsa_public_key() -> Result<()> {
+        let mut reader = Reader::new(&RSA_PUBLIC_KEY);
+        let mut key = reader.read_sequence()?;
+        let n = key.read_integer()?;
+        let e = key.read_integer()?;
+
+        assert!(key.is_empty() && reader.is_empty());
+        assert_eq!(n.len(), 64);
+        assert_eq!(n[0], 0xc0);
+        assert_eq!(e, vec![1, 0, 1]);
+
+        let mut writer = Writer::new();
+        writer.write_sequence(|w| {
+            w.write_integer(&n);
+            w.write_integer(&e);
+            Ok(())
+        })?;
+        assert_eq!(writer.into_vec(), RSA_PUBLIC_KEY.to_vec());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest_info() -> Result<()> {
+        // PKCS#1 v1.5 DigestInfo prefix for SHA-256
+        let prefix = [
+            0x30, 0x31, 0x30, 0x0d, 0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02,
+            0x01, 0x05, 0x00, 0x04, 0x20,
+        ];
+        let sha256 = [2, 16, 840, 1, 101, 3, 4, 2, 1];
+
+        let mut writer = Writer::new();
+        writer.write_sequence(|w| {
+            w.write_sequence(|w| {
+                w.write_oid(&sha256)?;
+                w.write_null();
+                Ok(())
+            })?;
+            w.write_octet_string(&[0xaa; 32]);
+            Ok(())
+        })?;
+        let encoded = writer.into_vec();
+        assert_eq!(&encoded[..prefix.len()], &prefix[..]);
+
+        let mut digest_info = Reader::new(&encoded).read_sequence()?;
+        let mut algorithm = digest_info.read_sequence()?;
+        // A mismatched tag leaves the element in place
+        assert!(algorithm.read_null().is_err());
+        assert_eq!(algorithm.read_oid()?, sha256.to_vec());
+        algorithm.read_null()?;
+        assert_eq!(digest_info.read_octet_string()?, &[0xaa; 32][..]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_oid_rejects_invalid_arcs() {
+        for arcs in [&[][..], &[1], &[3, 1], &[1, 40]] {
+            assert!(Writer::new().write_oid(arcs).is_err());
+        }
+        assert!(Writer::new().write_oid(&[2, 100, 3]).is_ok());
+    }
+
+    #[test]
+    fn test_read_oid_rejects_empty() {
+        assert!(Reader::new(&[OBJECT_IDENTIFIER, 0x00]).read_oid().is_err());
+    }
+
+    #[test]
+    fn test_read_oid_rejects_non_minimal_arc() {
+        // 1.2.840 with the 840 arc encoded as 0x80 0x86 0x48
+        let encoded = [OBJECT_IDENTIFIER, 0x04, 0x2a, 0x80, 0x86, 0x48];
+        assert!(Reader::new(&encoded).read_oid().is_err());
+        assert_eq!(
+            Reader::new(&[OBJECT_IDENTIFIER, 0x03, 0x2a, 0x86, 0x48])
+                .read_oid()
+                .unwrap(),
+            vec![1, 2, 840]
+        );
+    }
+
+    #[test]
+    fn test_read_oid_rejects_overflowing_arc() {
+        // 1.2 followed by an arc of ten 7 bit groups, 70 bits long
+        let mut encoded = vec![OBJECT_IDENTIFIER, 0x0b, 0x2a];
+        encoded.extend_from_slice(&[0xff; 9]);
+        encoded.push(0x7f);
+        assert!(Reader::new(&encoded).read_oid().is_err());
+
+        let mut writer = Writer::new();
+        writer.write_oid(&[1, 2, u64::MAX]).unwrap();
+        assert_eq!(
+            Reader::new(&writer.into_vec()).read_oid().unwrap(),
+            vec![1, 2, u64::MAX]
+        );
+    }
+
+    #[test]
+    fn test_long_form_length() -> Result<()> {
+        let mut writer = Writer::new();
+        writer.write_octet_string(&[0; 300]);
+        let encoded = writer.into_vec();
+
+        assert_eq!(&encoded[..4], &[OCTET_STRING, 0x82, 0x01, 0x2c]);
+        assert_eq!(Reader::new(&encoded).read_octet_string()?.len(), 300);
+        // Non minimal length encoding is not DER
+        assert!(Reader::new(&[OCTET_STRING, 0x81, 0x01, 0x00])
+            .read_tlv()
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+extern crate base32;
+extern crate base58;
+extern crate base64;
+extern crate base85;
+extern crate hex;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+/// Text encoding of binary data, implemented by every codec crate in the repo.
+pub trait Encoding {
+    fn name(&self) -> &'static str;
+    fn string_to_vec_u8(&self, s: &str) -> Result<Vec<u8>>;
+    fn vec_u8_to_string(&self, vec: Vec<u8>) -> Result<String>;
+}
+
+pub struct Hex;
+
+impl Encoding for Hex {
+    fn name(&self) -> &'static str {
+        "hex"
+    }
+
+    fn string_to_vec_u8(&self, s: &str) -> Result<Vec<u8>> {
+        hex::string_to_vec_u8(s)
+    }
+
+    fn vec_u8_to_string(&self, vec: Vec<u8>) -> Result<String> {
+        Ok(hex::vec_u8_to_string(vec))
+    }
+}
+
+pub struct Base32;
+
+impl Encoding for Base32 {
+    fn name(&self) -> &'static str {
+        "base32"
+    }
+
+    fn string_to_vec_u8(&self, s: &str) -> Result<Vec<u8>> {
+        base32::string_to_vec_u8(s)
+    }
+
+    fn vec_u8_to_string(&self, vec: Vec<u8>) -> Result<String> {
+        Ok(base32::vec_u8_to_string(vec))
+    }
+}
+
+pub struct Base58;
+
+impl Encoding for Base58 {
+    fn name(&self) -> &'static str {
+        "base58"
+    }
+
+    fn string_to_vec_u8(&self, s: &str) -> Result<Vec<u8>> {
+        base58::string_to_vec_u8(s)
+    }
+
+    fn vec_u8_to_string(&self, vec: Vec<u8>) -> Result<String> {
+        Ok(base58::vec_u8_to_string(vec))
+    }
+}
+
+/// Base64 with any of the `base64` crate configs.
+pub struct Base64(pub base64::Config);
+
+impl Encoding for Base64 {
+    fn name(&self) -> &'static str {
+        match self.0 {
+            base64::STANDARD => "base64",
+            base64::STANDARD_NO_PAD => "base64 (unpadded)",
+            base64::URL_SAFE => "base64url",
+            base64::URL_SAFE_NO_PAD => "base64url (unpadded)",
+            base64::MIME => "base64 (MIME)",
+            _ => "base64 (custom)",
+        }
+    }
+
+    fn string_to_vec_u8(&self, s: &str) -> Result<Vec<u8>> {
+        base64::string_to_vec_u8_config(s, self.0)
+    }
+
+    fn vec_u8_to_string(&self, vec: Vec<u8>) -> Result<String> {
+        base64::vec_u8_to_string_config(vec, self.0)
+    }
+}
+
+pub struct Base85;
+
+impl Encoding for Base85 {
+    fn name(&self) -> &'static str {
+        "base85"
+    }
+
+    fn string_to_vec_u8(&self, s: &str) -> Result<Vec<u8>> {
+        base85::string_to_vec_u8(s)
+    }
+
+    fn vec_u8_to_string(&self, vec: Vec<u8>) -> Result<String> {
+        Ok(base85::vec_u8_to_string(vec))
+    }
+}
+
+/// Every supported encoding, handy to try them all on an unknown token.
+pub fn all() -> Vec<Box<dyn Encoding>> {
+    vec![
+        Box::new(Hex),
+        Box::new(Base32),
+        Box::new(Base58),
+        Box::new(Base64(base64::STANDARD)),
+        Box::new(Base64(base64::URL_SAFE_NO_PAD)),
+        Box::new(Base85),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_all() -> Result<()> {
+        let data = b"\x00\x00Tokens come in many shapes\xff".to_vec();
+
+        for encoding in all() {
+            let encoded = encoding.vec_u8_to_string(data.clone())?;
+            assert_eq!(
+                encoding.string_to_vec_u8(&encoded)?,
+                data,
+                "{}",
+                encoding.name()
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+//! Bulk throughput of the hex codec. Run `cargo bench`, and compare with
+//! `cargo bench --no-default-features` for the scalar baseline.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+fn report(name: &str, bytes: usize, f: impl Fn()) {
+    let rounds = 20;
+    let start = Instant::now();
+    for _ in 0..rounds {
+        f();
+    }
+    let secs = start.elapsed().as_secs_f64();
+
+    println!(
+        "{:<8} {:>10.1} MB/s",
+        name,
+        (bytes * rounds) as f64 / secs / 1_000_000.0
+    );
+}
+
+fn main() {
+    let data: Vec<u8> = (0..8 << 20).map(|i: usize| (i * 167 + 13) as u8).collect();
+    let encoded = hex::vec_u8_to_string(data.clone());
+
+    report("encode", data.len(), || {
+        black_box(hex::vec_u8_to_string(black_box(data.clone())));
+    });
+    report("decode", encoded.len(), || {
+        black_box(hex::string_to_vec_u8(black_box(&encoded)).unwrap());
+    });
+}
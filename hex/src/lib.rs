@@ -1,5 +1,7 @@
 use std::char;
 
+mod simd;
+
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
 #[cfg(test)]
@@ -17,26 +19,84 @@ mod tests {
 
     #[test]
     fn test_vec_u8_to_string() -> Result<()> {
-        assert_eq!("80ff", vec_u8_to_string(vec![128, 255])?);
+        assert_eq!("80ff", vec_u8_to_string(vec![128, 255]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_to_vec_u8_ct() -> Result<()> {
+        assert_eq!(vec![0x80, 0xff, 0x0a], string_to_vec_u8_ct("80fF0A")?);
+        assert!(string_to_vec_u8_ct("80fg").is_err());
+        assert!(string_to_vec_u8_ct("8/").is_err());
+        assert!(string_to_vec_u8_ct("8:").is_err());
 
         Ok(())
     }
+
+    /// Fastest of many interleaved rounds for each input, so both see the same machine load.
+    fn time_decodes(left: &str, right: &str) -> (std::time::Duration, std::time::Duration) {
+        let time = |input: &str| {
+            let start = std::time::Instant::now();
+            for _ in 0..200 {
+                std::hint::black_box(string_to_vec_u8_ct(std::hint::black_box(input)).unwrap());
+            }
+            start.elapsed()
+        };
+
+        (0..50).fold(
+            (std::time::Duration::MAX, std::time::Duration::MAX),
+            |(l, r), _| (l.min(time(left)), r.min(time(right))),
+        )
+    }
+
+    #[test]
+    #[ignore = "measures wall-clock time, run alone with --ignored"]
+    fn test_string_to_vec_u8_ct_timing() {
+        let digits = "0123456789".repeat(100);
+        let letters = "abcdefABCD".repeat(100);
+
+        let (t_digits, t_letters) = time_decodes(&digits, &letters);
+        let ratio = t_digits.as_secs_f64() / t_letters.as_secs_f64();
+
+        assert!(
+            (0.8..1.25).contains(&ratio),
+            "Decoding time depends on input characters: {:?} vs {:?}",
+            t_digits,
+            t_letters
+        );
+    }
 }
 
 pub fn string_to_vec_u8(s: &str) -> Result<Vec<u8>> {
+    decode(s, cfg!(feature = "simd"))
+}
+
+fn decode(s: &str, use_simd: bool) -> Result<Vec<u8>> {
     if s.len() % 2 != 0 {
         return Err(format!("Input length is odd, should be even {}", s.len()).into());
     }
 
-    let mut as_bytes = Vec::with_capacity(s.len());
-    for c in s.chars() {
-        as_bytes.push(hexchar_to_u8(c)?);
+    let mut out = Vec::with_capacity(s.len() / 2);
+
+    // The fast path stops before the first block holding an invalid character, always on a
+    // char boundary, and leaves the rest and the error reporting to the scalar loop.
+    let done = match use_simd {
+        true => simd::decode(s.as_bytes(), &mut out),
+        false => 0,
+    };
+
+    let mut chars = s[done..].chars();
+    while let Some(high) = chars.next() {
+        let high = hexchar_to_u8(high)?;
+        let low = match chars.next() {
+            Some(c) => hexchar_to_u8(c)?,
+            None => return Err("Input has an odd number of characters".into()),
+        };
+        out.push((high << 4) | low);
     }
 
-    Ok(as_bytes
-        .chunks(2)
-        .map(|p| (p[0] << 4) | p[1])
-        .collect::<Vec<u8>>())
+    Ok(out)
 }
 
 fn hexchar_to_u8(c: char) -> Result<u8> {
@@ -46,12 +106,65 @@ fn hexchar_to_u8(c: char) -> Result<u8> {
     }
 }
 
+/// Same as `string_to_vec_u8`, but in time independent of the decoded bytes, for keys and MACs.
+/// Only the length of the input leaks; an invalid character is reported without its value.
+pub fn string_to_vec_u8_ct(s: &str) -> Result<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return Err(format!("Input length is odd, should be even {}", s.len()).into());
+    }
+
+    let mut invalid = 0u8;
+    let mut out = Vec::with_capacity(s.len() / 2);
+    for pair in s.as_bytes().chunks(2) {
+        let (high, high_valid) = hexchar_to_u8_ct(pair[0]);
+        let (low, low_valid) = hexchar_to_u8_ct(pair[1]);
+        invalid |= !(high_valid & low_valid);
+        out.push((high << 4) | low);
+    }
+
+    if invalid != 0 {
+        return Err("Invalid character (not hex) in input".into());
+    }
+
+    Ok(out)
+}
+
+/// 0xff if `lo <= c <= hi`, 0 otherwise, without branching on `c`.
+fn ct_range_mask(c: u8, lo: u8, hi: u8) -> u8 {
+    let c = c as i16;
+    (((lo as i16 - 1 - c) & (c - hi as i16 - 1)) >> 8) as u8
+}
+
+/// Returns the nibble value and a 0xff mask if `c` was a hex digit.
+fn hexchar_to_u8_ct(c: u8) -> (u8, u8) {
+    let digit = ct_range_mask(c, b'0', b'9');
+    let lower = ct_range_mask(c, b'a', b'f');
+    let upper = ct_range_mask(c, b'A', b'F');
+
+    let value = (digit & c.wrapping_sub(b'0'))
+        | (lower & c.wrapping_sub(b'a' - 10))
+        | (upper & c.wrapping_sub(b'A' - 10));
+
+    (value, digit | lower | upper)
+}
+
 pub fn vec_u8_to_string(vec: Vec<u8>) -> String {
-    let mut out_string = String::with_capacity(vec.len() * 2);
-    for b in vec {
-        out_string.push(char::from_digit(u32::from(b >> 4), 16).unwrap());
-        out_string.push(char::from_digit(u32::from(b & 0x0f), 16).unwrap());
+    encode(&vec, cfg!(feature = "simd"))
+}
+
+fn encode(vec: &[u8], use_simd: bool) -> String {
+    let mut out = Vec::with_capacity(vec.len() * 2);
+
+    let done = match use_simd {
+        true => simd::encode(vec, &mut out),
+        false => 0,
+    };
+
+    for &b in &vec[done..] {
+        out.push(char::from_digit(u32::from(b >> 4), 16).unwrap() as u8);
+        out.push(char::from_digit(u32::from(b & 0x0f), 16).unwrap() as u8);
     }
 
-    out_string
+    // Only ASCII hex digits were pushed
+    String::from_utf8(out).unwrap()
 }
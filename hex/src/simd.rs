@@ -0,0 +1,286 @@
+//! SSE2 and AVX2 fast paths, picked at runtime. Both functions only process whole blocks and
+//! return how many input bytes they consumed, the caller finishes with the scalar code.
+
+/// Decodes whole blocks of hex characters into `out`, stopping before any invalid block.
+pub(crate) fn decode(input: &[u8], out: &mut Vec<u8>) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return unsafe { x86::decode_avx2(input, out) };
+        }
+        if is_x86_feature_detected!("sse2") {
+            return unsafe { x86::decode_sse2(input, out) };
+        }
+    }
+
+    let _ = (input, out);
+    0
+}
+
+/// Encodes whole blocks of bytes as lowercase hex characters into `out`.
+pub(crate) fn encode(input: &[u8], out: &mut Vec<u8>) -> usize {
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return unsafe { x86::encode_avx2(input, out) };
+        }
+        if is_x86_feature_detected!("sse2") {
+            return unsafe { x86::encode_sse2(input, out) };
+        }
+    }
+
+    let _ = (input, out);
+    0
+}
+
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+mod x86 {
+    #[cfg(target_arch = "x86")]
+    use std::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use std::arch::x86_64::*;
+
+    /// Nibble values of 16 hex characters, and a mask of the valid ones.
+    #[target_feature(enable = "sse2")]
+    unsafe fn nibbles_sse2(v: __m128i) -> (__m128i, __m128i) {
+        // Signed compares, so anything above 0x7f is never in range
+        let digit = _mm_and_si128(
+            _mm_cmpgt_epi8(v, _mm_set1_epi8(b'0' as i8 - 1)),
+            _mm_cmpgt_epi8(_mm_set1_epi8(b'9' as i8 + 1), v),
+        );
+        let lower = _mm_or_si128(v, _mm_set1_epi8(0x20));
+        let alpha = _mm_and_si128(
+            _mm_cmpgt_epi8(lower, _mm_set1_epi8(b'a' as i8 - 1)),
+            _mm_cmpgt_epi8(_mm_set1_epi8(b'f' as i8 + 1), lower),
+        );
+
+        let value = _mm_or_si128(
+            _mm_and_si128(digit, _mm_sub_epi8(v, _mm_set1_epi8(b'0' as i8))),
+            _mm_and_si128(alpha, _mm_sub_epi8(lower, _mm_set1_epi8(b'a' as i8 - 10))),
+        );
+
+        (value, _mm_or_si128(digit, alpha))
+    }
+
+    /// Merges each pair of nibbles into the low byte of its 16 bits lane.
+    #[target_feature(enable = "sse2")]
+    unsafe fn merge_sse2(v: __m128i) -> __m128i {
+        // Little endian: the high nibble is in the low byte of the lane
+        _mm_and_si128(
+            _mm_or_si128(_mm_slli_epi16(v, 4), _mm_srli_epi16(v, 8)),
+            _mm_set1_epi16(0xff),
+        )
+    }
+
+    #[target_feature(enable = "sse2")]
+    unsafe fn ascii_sse2(n: __m128i) -> __m128i {
+        let letters = _mm_and_si128(
+            _mm_cmpgt_epi8(n, _mm_set1_epi8(9)),
+            _mm_set1_epi8((b'a' - b'0' - 10) as i8),
+        );
+        _mm_add_epi8(n, _mm_add_epi8(_mm_set1_epi8(b'0' as i8), letters))
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn decode_sse2(input: &[u8], out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 16];
+
+        while i + 32 <= input.len() {
+            let a = _mm_loadu_si128(input.as_ptr().add(i) as *const __m128i);
+            let b = _mm_loadu_si128(input.as_ptr().add(i + 16) as *const __m128i);
+            let (a, a_valid) = nibbles_sse2(a);
+            let (b, b_valid) = nibbles_sse2(b);
+
+            if _mm_movemask_epi8(_mm_and_si128(a_valid, b_valid)) != 0xffff {
+                break;
+            }
+
+            let packed = _mm_packus_epi16(merge_sse2(a), merge_sse2(b));
+            _mm_storeu_si128(block.as_mut_ptr() as *mut __m128i, packed);
+            out.extend_from_slice(&block);
+            i += 32;
+        }
+
+        i
+    }
+
+    #[target_feature(enable = "sse2")]
+    pub unsafe fn encode_sse2(input: &[u8], out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 32];
+
+        while i + 16 <= input.len() {
+            let v = _mm_loadu_si128(input.as_ptr().add(i) as *const __m128i);
+            let high = ascii_sse2(_mm_and_si128(_mm_srli_epi16(v, 4), _mm_set1_epi8(0x0f)));
+            let low = ascii_sse2(_mm_and_si128(v, _mm_set1_epi8(0x0f)));
+
+            let ptr = block.as_mut_ptr() as *mut __m128i;
+            _mm_storeu_si128(ptr, _mm_unpacklo_epi8(high, low));
+            _mm_storeu_si128(ptr.add(1), _mm_unpackhi_epi8(high, low));
+            out.extend_from_slice(&block);
+            i += 16;
+        }
+
+        i
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn nibbles_avx2(v: __m256i) -> (__m256i, __m256i) {
+        let digit = _mm256_and_si256(
+            _mm256_cmpgt_epi8(v, _mm256_set1_epi8(b'0' as i8 - 1)),
+            _mm256_cmpgt_epi8(_mm256_set1_epi8(b'9' as i8 + 1), v),
+        );
+        let lower = _mm256_or_si256(v, _mm256_set1_epi8(0x20));
+        let alpha = _mm256_and_si256(
+            _mm256_cmpgt_epi8(lower, _mm256_set1_epi8(b'a' as i8 - 1)),
+            _mm256_cmpgt_epi8(_mm256_set1_epi8(b'f' as i8 + 1), lower),
+        );
+
+        let value = _mm256_or_si256(
+            _mm256_and_si256(digit, _mm256_sub_epi8(v, _mm256_set1_epi8(b'0' as i8))),
+            _mm256_and_si256(
+                alpha,
+                _mm256_sub_epi8(lower, _mm256_set1_epi8(b'a' as i8 - 10)),
+            ),
+        );
+
+        (value, _mm256_or_si256(digit, alpha))
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn merge_avx2(v: __m256i) -> __m256i {
+        _mm256_and_si256(
+            _mm256_or_si256(_mm256_slli_epi16(v, 4), _mm256_srli_epi16(v, 8)),
+            _mm256_set1_epi16(0xff),
+        )
+    }
+
+    #[target_feature(enable = "avx2")]
+    unsafe fn ascii_avx2(n: __m256i) -> __m256i {
+        let letters = _mm256_and_si256(
+            _mm256_cmpgt_epi8(n, _mm256_set1_epi8(9)),
+            _mm256_set1_epi8((b'a' - b'0' - 10) as i8),
+        );
+        _mm256_add_epi8(n, _mm256_add_epi8(_mm256_set1_epi8(b'0' as i8), letters))
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn decode_avx2(input: &[u8], out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 32];
+
+        while i + 64 <= input.len() {
+            let a = _mm256_loadu_si256(input.as_ptr().add(i) as *const __m256i);
+            let b = _mm256_loadu_si256(input.as_ptr().add(i + 32) as *const __m256i);
+            let (a, a_valid) = nibbles_avx2(a);
+            let (b, b_valid) = nibbles_avx2(b);
+
+            if _mm256_movemask_epi8(_mm256_and_si256(a_valid, b_valid)) != -1 {
+                break;
+            }
+
+            // Packing works per 128 bits lane, put the 64 bits quarters back in order
+            let packed = _mm256_packus_epi16(merge_avx2(a), merge_avx2(b));
+            let packed = _mm256_permute4x64_epi64(packed, 0b11_01_10_00);
+            _mm256_storeu_si256(block.as_mut_ptr() as *mut __m256i, packed);
+            out.extend_from_slice(&block);
+            i += 64;
+        }
+
+        i
+    }
+
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn encode_avx2(input: &[u8], out: &mut Vec<u8>) -> usize {
+        let mut i = 0;
+        let mut block = [0u8; 64];
+
+        while i + 32 <= input.len() {
+            let v = _mm256_loadu_si256(input.as_ptr().add(i) as *const __m256i);
+            let high = ascii_avx2(_mm256_and_si256(
+                _mm256_srli_epi16(v, 4),
+                _mm256_set1_epi8(0x0f),
+            ));
+            let low = ascii_avx2(_mm256_and_si256(v, _mm256_set1_epi8(0x0f)));
+
+            // Unpacking works per 128 bits lane as well
+            let first = _mm256_unpacklo_epi8(high, low);
+            let second = _mm256_unpackhi_epi8(high, low);
+
+            let ptr = block.as_mut_ptr() as *mut __m256i;
+            _mm256_storeu_si256(ptr, _mm256_permute2x128_si256(first, second, 0x20));
+            _mm256_storeu_si256(ptr.add(1), _mm256_permute2x128_si256(first, second, 0x31));
+            out.extend_from_slice(&block);
+            i += 32;
+        }
+
+        i
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 167 + 13) as u8).collect()
+    }
+
+    #[test]
+    fn test_simd_matches_scalar() {
+        for len in 0..100 {
+            let data = sample(len);
+            let scalar = crate::encode(&data, false);
+
+            assert_eq!(crate::encode(&data, true), scalar);
+            assert_eq!(crate::decode(&scalar, true).unwrap(), data);
+            assert_eq!(crate::decode(&scalar.to_uppercase(), true).unwrap(), data);
+        }
+    }
+
+    #[test]
+    fn test_simd_rejects_invalid() {
+        let valid = crate::encode(&sample(64), false);
+
+        for i in 0..valid.len() - 1 {
+            for bad in ["g", "/", ":", "@", "G", "`", " "] {
+                let mut invalid = valid.clone();
+                invalid.replace_range(i..i + 1, bad);
+                assert!(crate::decode(&invalid, true).is_err(), "{}", invalid);
+            }
+
+            // Two bytes character, keeps the length even
+            let mut invalid = valid.clone();
+            invalid.replace_range(i..i + 2, "\u{e9}");
+            assert!(crate::decode(&invalid, true).is_err(), "{}", invalid);
+        }
+    }
+
+    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[test]
+    fn test_each_instruction_set() {
+        let data = sample(100);
+        let encoded = crate::encode(&data, false);
+
+        if is_x86_feature_detected!("sse2") {
+            let (mut chars, mut bytes) = (Vec::new(), Vec::new());
+            let done = unsafe { x86::encode_sse2(&data, &mut chars) };
+            assert_eq!(done, 96);
+            assert_eq!(&chars[..], &encoded.as_bytes()[..192]);
+            let done = unsafe { x86::decode_sse2(encoded.as_bytes(), &mut bytes) };
+            assert_eq!(done, 192);
+            assert_eq!(&bytes[..], &data[..96]);
+        }
+
+        if is_x86_feature_detected!("avx2") {
+            let (mut chars, mut bytes) = (Vec::new(), Vec::new());
+            let done = unsafe { x86::encode_avx2(&data, &mut chars) };
+            assert_eq!(done, 96);
+            assert_eq!(&chars[..], &encoded.as_bytes()[..192]);
+            let done = unsafe { x86::decode_avx2(encoded.as_bytes(), &mut bytes) };
+            assert_eq!(done, 192);
+            assert_eq!(&bytes[..], &data[..96]);
+        }
+    }
+}
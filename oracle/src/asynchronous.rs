@@ -0,0 +1,134 @@
+use crate::{EncryptionOracle, Result};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+/// Asynchronous counterpart of `EncryptionOracle`, so attacks can keep several queries in
+/// flight against slow targets.
+pub trait AsyncEncryptionOracle {
+    fn encrypt(&self, data: &[u8]) -> impl Future<Output = Result<Vec<u8>>> + Send;
+}
+
+/// Runs a synchronous oracle on tokio's blocking threads.
+pub struct Blocking<O> {
+    inner: Arc<O>,
+}
+
+impl<O> Blocking<O> {
+    pub fn new(inner: Arc<O>) -> Self {
+        Blocking { inner }
+    }
+}
+
+impl<O: EncryptionOracle + Send + Sync + 'static> AsyncEncryptionOracle for Blocking<O> {
+    async fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let inner = self.inner.clone();
+        let data = data.to_vec();
+
+        tokio::task::spawn_blocking(move || inner.encrypt(&data)).await?
+    }
+}
+
+struct Connection {
+    reader: BufReader<OwnedReadHalf>,
+    writer: OwnedWriteHalf,
+}
+
+/// Asynchronous client of an `OracleServer`. Concurrent queries each use their own connection,
+/// taken from a pool that grows as needed.
+pub struct AsyncRemoteOracle {
+    addr: std::net::SocketAddr,
+    pool: Mutex<Vec<Connection>>,
+}
+
+impl AsyncRemoteOracle {
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let connection = TcpStream::connect(addr).await?;
+        let addr = connection.peer_addr()?;
+
+        let oracle = AsyncRemoteOracle {
+            addr,
+            pool: Mutex::new(Vec::new()),
+        };
+        oracle.release(Self::split(connection)?);
+
+        Ok(oracle)
+    }
+
+    fn split(stream: TcpStream) -> Result<Connection> {
+        stream.set_nodelay(true)?;
+        let (reader, writer) = stream.into_split();
+
+        Ok(Connection {
+            reader: BufReader::new(reader),
+            writer,
+        })
+    }
+
+    async fn acquire(&self) -> Result<Connection> {
+        let pooled = self.pool.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        match pooled {
+            Some(connection) => Ok(connection),
+            None => Self::split(TcpStream::connect(self.addr).await?),
+        }
+    }
+
+    fn release(&self, connection: Connection) {
+        self.pool
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(connection);
+    }
+
+    async fn query(&self, command: &str, data: &[u8]) -> Result<String> {
+        let mut connection = self.acquire().await?;
+
+        let request = format!("{} {}\n", command, hex::vec_u8_to_string(data.to_vec()));
+        connection.writer.write_all(request.as_bytes()).await?;
+
+        let mut line = String::new();
+        if connection.reader.read_line(&mut line).await? == 0 {
+            return Err("Oracle server closed the connection".into());
+        }
+        // Only connections in a clean state go back to the pool
+        self.release(connection);
+
+        crate::remote::parse_response(&line)
+    }
+}
+
+impl AsyncEncryptionOracle for AsyncRemoteOracle {
+    async fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        hex::string_to_vec_u8(&self.query("ENCRYPT", data).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OracleChallenge12, OracleServer};
+
+    #[tokio::test]
+    async fn test_concurrent_remote_queries() -> Result<()> {
+        let served = Arc::new(OracleChallenge12::with_seed(40)?);
+        let addr = OracleServer::new()
+            .encryption(served.clone())
+            .spawn("127.0.0.1:0")?;
+
+        let remote = AsyncRemoteOracle::connect(addr).await?;
+        let local = Blocking::new(served.clone());
+
+        let (a, b, c) = tokio::join!(
+            remote.encrypt(b"a"),
+            remote.encrypt(b"b"),
+            local.encrypt(b"c")
+        );
+        assert_eq!(a?, served.encrypt(b"a")?);
+        assert_eq!(b?, served.encrypt(b"b")?);
+        assert_eq!(c?, served.encrypt(b"c")?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+//! Serves a challenge oracle on localhost, e.g. `oracle_server 17 127.0.0.1:7878`. Keys are drawn
+//! from the run seed, set `CRYPTOPALS_SEED` to serve the same oracle again.
+
+use oracle::{
+    OracleChallenge12, OracleChallenge13, OracleChallenge14, OracleChallenge17, OracleServer,
+};
+use std::env;
+use std::net::TcpListener;
+use std::sync::Arc;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+fn main() -> Result<()> {
+    let mut args = env::args().skip(1);
+    let challenge = args.next().unwrap_or_default();
+    let addr = args.next().unwrap_or_else(|| "127.0.0.1:7878".to_string());
+
+    let server = match challenge.as_str() {
+        "12" => OracleServer::new().encryption(Arc::new(OracleChallenge12::new()?)),
+        "13" => {
+            let oracle = Arc::new(OracleChallenge13::new()?);
+            OracleServer::new()
+                .encryption(oracle.clone())
+                .verification(oracle)
+        }
+        "14" => OracleServer::new().encryption(Arc::new(OracleChallenge14::new()?)),
+        "17" => {
+            let oracle = Arc::new(OracleChallenge17::new()?);
+            OracleServer::new()
+                .encryption(oracle.clone())
+                .padding(oracle)
+        }
+        _ => return Err("Usage: oracle_server <12|13|14|17> [address]".into()),
+    };
+
+    let listener = TcpListener::bind(&addr)?;
+    println!(
+        "Serving challenge {} oracle on {} (seed {})",
+        challenge,
+        listener.local_addr()?,
+        oracle::run_seed()?
+    );
+
+    server.serve(listener)
+}
@@ -0,0 +1,306 @@
+use crate::{AesOracle, OracleRng, Result};
+use rand::Rng;
+use std::ops::Range;
+use std::sync::Mutex;
+
+/// How an `AesOracle` chooses the IV of its CBC encryptions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IvPolicy {
+    /// Always use the given IV.
+    Fixed([u8; 16]),
+    /// Draw one random IV when the oracle is built.
+    RandomPerOracle,
+    /// Draw a fresh IV on every encryption and prepend it to the ciphertext.
+    RandomPerCall,
+    /// Reuse the key as IV, only possible with 16 bytes keys.
+    Key,
+}
+
+/// How an `AesOracle` pads its plaintexts to the block size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Padding {
+    /// `aes::padding_pkcs7`.
+    Pkcs7,
+    /// Zero bytes up to the next block boundary.
+    Zero,
+    /// No padding, unaligned plaintexts are rejected.
+    None,
+}
+
+/// Transforms or rejects the attacker input before the oracle encrypts it.
+pub type InputFilter = Box<dyn Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync>;
+
+pub(crate) enum Affix {
+    Fixed(Vec<u8>),
+    Random(Range<usize>),
+    RandomPerCall(Range<usize>),
+}
+
+impl Affix {
+    pub(crate) fn generate(&self, rng: &mut impl Rng) -> Vec<u8> {
+        match self {
+            Affix::Fixed(bytes) => bytes.clone(),
+            Affix::Random(len) | Affix::RandomPerCall(len) => {
+                let len = rng.gen_range(len.clone());
+                (0..len).map(|_| rng.gen()).collect()
+            }
+        }
+    }
+
+    fn validate(&self, name: &str) -> Result<()> {
+        match self {
+            Affix::Random(len) | Affix::RandomPerCall(len) if len.is_empty() => {
+                Err(format!("Empty {} length range {:?}", name, len).into())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Draws the affixes which are random per oracle, per call ones are left to the oracle.
+    fn resolve(self, rng: &mut impl Rng) -> Affix {
+        match self {
+            Affix::Random(_) => Affix::Fixed(self.generate(rng)),
+            affix => affix,
+        }
+    }
+}
+
+/// Configures and builds an `AesOracle`. By default it encrypts with a random 16 bytes key, a
+/// random mode between ECB and CBC, a random IV per oracle, PKCS#7 padding and no prefix nor
+/// suffix. CTR mode ignores the IV and padding settings. Randomness
+/// comes from the run seed unless a seed or generator is given.
+pub struct OracleBuilder {
+    mode: Option<aes::MODE>,
+    key_size: usize,
+    prefix: Affix,
+    suffix: Affix,
+    iv_policy: IvPolicy,
+    padding: Padding,
+    nonce: Option<u64>,
+    reject_high_ascii: bool,
+    filters: Vec<InputFilter>,
+    rng: Option<OracleRng>,
+}
+
+impl Default for OracleBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OracleBuilder {
+    pub fn new() -> Self {
+        OracleBuilder {
+            mode: None,
+            key_size: 16,
+            prefix: Affix::Fixed(Vec::new()),
+            suffix: Affix::Fixed(Vec::new()),
+            iv_policy: IvPolicy::RandomPerOracle,
+            padding: Padding::Pkcs7,
+            nonce: None,
+            reject_high_ascii: false,
+            filters: Vec::new(),
+            rng: None,
+        }
+    }
+
+    /// Fixes the mode, otherwise it is picked at random when building.
+    pub fn mode(mut self, mode: aes::MODE) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Key size in bytes: 16, 24 or 32.
+    pub fn key_size(mut self, key_size: usize) -> Self {
+        self.key_size = key_size;
+        self
+    }
+
+    pub fn prefix(mut self, prefix: Vec<u8>) -> Self {
+        self.prefix = Affix::Fixed(prefix);
+        self
+    }
+
+    /// Random bytes prefix, its length drawn from `len` when building.
+    pub fn random_prefix(mut self, len: Range<usize>) -> Self {
+        self.prefix = Affix::Random(len);
+        self
+    }
+
+    /// Random bytes prefix drawn again on every encryption, its length drawn from `len`.
+    pub fn random_prefix_per_call(mut self, len: Range<usize>) -> Self {
+        self.prefix = Affix::RandomPerCall(len);
+        self
+    }
+
+    pub fn suffix(mut self, suffix: Vec<u8>) -> Self {
+        self.suffix = Affix::Fixed(suffix);
+        self
+    }
+
+    /// Random bytes suffix, its length drawn from `len` when building.
+    pub fn random_suffix(mut self, len: Range<usize>) -> Self {
+        self.suffix = Affix::Random(len);
+        self
+    }
+
+    pub fn iv_policy(mut self, iv_policy: IvPolicy) -> Self {
+        self.iv_policy = iv_policy;
+        self
+    }
+
+    pub fn padding(mut self, padding: Padding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Fixes the CTR nonce, otherwise it is drawn at random when building.
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = Some(nonce);
+        self
+    }
+
+    /// Makes decryption fail with `InvalidAscii` when the plaintext holds bytes above 127, like a
+    /// receiver validating its input.
+    pub fn reject_high_ascii(mut self) -> Self {
+        self.reject_high_ascii = true;
+        self
+    }
+
+    /// Adds a filter applied to the attacker input, in the order filters were added.
+    pub fn filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&[u8]) -> Result<Vec<u8>> + Send + Sync + 'static,
+    {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        self.rng(crate::seeded_rng(seed))
+    }
+
+    /// Draws the key, mode, IVs and random affixes from `rng`.
+    pub fn rng(mut self, rng: OracleRng) -> Self {
+        self.rng = Some(rng);
+        self
+    }
+
+    pub fn build(self) -> Result<AesOracle> {
+        let mut rng = match self.rng {
+            Some(rng) => rng,
+            None => crate::rng()?,
+        };
+
+        if ![16, 24, 32].contains(&self.key_size) {
+            return Err(format!(
+                "Invalid AES key size, should be 16, 24 or 32, is {}.",
+                self.key_size
+            )
+            .into());
+        }
+        self.prefix.validate("prefix")?;
+        self.suffix.validate("suffix")?;
+
+        let key: Vec<u8> = (0..self.key_size).map(|_| rng.gen()).collect();
+
+        // If given a mode, set it, otherwise randomly choose it.
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None => match rng.gen_range(0..2) {
+                0u32 => aes::MODE::ECB,
+                _ => aes::MODE::CBC,
+            },
+        };
+
+        let iv = match self.iv_policy {
+            IvPolicy::Fixed(iv) => Some(iv),
+            IvPolicy::RandomPerOracle => Some(rng.gen()),
+            IvPolicy::RandomPerCall => None,
+            IvPolicy::Key => {
+                let mut iv = [0u8; 16];
+                if key.len() != iv.len() {
+                    return Err("IV = key requires a 16 bytes key".into());
+                }
+                iv.copy_from_slice(&key);
+                Some(iv)
+            }
+        };
+
+        let nonce = match (&mode, self.nonce) {
+            (_, Some(nonce)) => nonce,
+            (aes::MODE::CTR, None) => rng.gen(),
+            (_, None) => 0,
+        };
+
+        Ok(AesOracle {
+            mode,
+            key,
+            iv_policy: self.iv_policy,
+            iv,
+            nonce,
+            reject_high_ascii: self.reject_high_ascii,
+            prefix: self.prefix.resolve(&mut rng),
+            suffix: self.suffix.generate(&mut rng),
+            padding: self.padding,
+            filters: self.filters,
+            rng: Mutex::new(rng),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DecryptionOracle, EditOracle, EncryptionOracle};
+
+    #[test]
+    fn test_builder_settings() -> Result<()> {
+        let oracle = OracleBuilder::new()
+            .mode(aes::MODE::CBC)
+            .key_size(32)
+            .prefix(b"prefix".to_vec())
+            .suffix(b"suffix".to_vec())
+            .iv_policy(IvPolicy::RandomPerCall)
+            .seed(7)
+            .filter(|data| Ok(data.to_ascii_uppercase()))
+            .build()?;
+
+        let first = oracle.encrypt(b"data")?;
+        assert_eq!(first.len(), 48);
+        assert_ne!(first, oracle.encrypt(b"data")?);
+        assert_eq!(&oracle.decrypt(&first)?[..16], b"prefixDATAsuffix");
+
+        let random_prefix = OracleBuilder::new()
+            .random_prefix_per_call(0..48)
+            .seed(7)
+            .build()?;
+        let len = random_prefix.encrypt(b"")?.len();
+        assert!((0..20).any(|_| random_prefix.encrypt(b"").unwrap().len() != len));
+
+        let ctr = OracleBuilder::new().mode(aes::MODE::CTR).seed(7).build()?;
+        let cipher = ctr.encrypt(b"unaligned")?;
+        assert_eq!(cipher.len(), 9);
+        assert_eq!(ctr.decrypt(&ctr.edit(&cipher, 2, b"ED")?)?, b"unEDigned");
+
+        assert!(OracleBuilder::new()
+            .padding(Padding::None)
+            .build()?
+            .encrypt(b"unaligned")
+            .is_err());
+        assert!(OracleBuilder::new()
+            .key_size(24)
+            .iv_policy(IvPolicy::Key)
+            .build()
+            .is_err());
+        assert!(OracleBuilder::new().random_prefix(0..0).build().is_err());
+        #[allow(clippy::reversed_empty_ranges)]
+        let reversed = 5..3;
+        assert!(OracleBuilder::new()
+            .random_suffix(reversed)
+            .build()
+            .is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{DecryptionOracle, EncryptionOracle, PaddingOracle, Result, VerificationOracle};
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Returned once a `CountingOracle` has answered as many queries as its budget allows.
+#[derive(Debug)]
+pub struct BudgetExceeded {
+    pub budget: usize,
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Oracle query budget of {} exhausted", self.budget)
+    }
+}
+
+impl Error for BudgetExceeded {}
+
+/// Snapshot of the queries a `CountingOracle` answered.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct QueryStats {
+    pub encrypt_calls: usize,
+    pub decrypt_calls: usize,
+    pub padding_calls: usize,
+    pub verify_calls: usize,
+    pub bytes_sent: usize,
+    pub bytes_received: usize,
+}
+
+impl QueryStats {
+    pub fn queries(&self) -> usize {
+        self.encrypt_calls + self.decrypt_calls + self.padding_calls + self.verify_calls
+    }
+}
+
+impl fmt::Display for QueryStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} queries ({} encrypt, {} decrypt, {} padding, {} verify), \
+{} bytes sent, {} bytes received",
+            self.queries(),
+            self.encrypt_calls,
+            self.decrypt_calls,
+            self.padding_calls,
+            self.verify_calls,
+            self.bytes_sent,
+            self.bytes_received
+        )
+    }
+}
+
+/// Wraps an oracle to count the queries made to it, and optionally cap them. It offers the
+/// same capabilities as the wrapped oracle.
+pub struct CountingOracle<O> {
+    inner: O,
+    budget: Option<usize>,
+    /// Queries of any kind, reserved against the budget before the per kind counters move.
+    queries: AtomicUsize,
+    encrypt_calls: AtomicUsize,
+    decrypt_calls: AtomicUsize,
+    padding_calls: AtomicUsize,
+    verify_calls: AtomicUsize,
+    bytes_sent: AtomicUsize,
+    bytes_received: AtomicUsize,
+}
+
+impl<O> CountingOracle<O> {
+    pub fn new(inner: O) -> Self {
+        CountingOracle {
+            inner,
+            budget: None,
+            queries: AtomicUsize::new(0),
+            encrypt_calls: AtomicUsize::new(0),
+            decrypt_calls: AtomicUsize::new(0),
+            padding_calls: AtomicUsize::new(0),
+            verify_calls: AtomicUsize::new(0),
+            bytes_sent: AtomicUsize::new(0),
+            bytes_received: AtomicUsize::new(0),
+        }
+    }
+
+    /// Fails every query past the first `budget` ones with `BudgetExceeded`.
+    pub fn with_budget(inner: O, budget: usize) -> Self {
+        let mut oracle = CountingOracle::new(inner);
+        oracle.budget = Some(budget);
+
+        oracle
+    }
+
+    pub fn stats(&self) -> QueryStats {
+        QueryStats {
+            encrypt_calls: self.encrypt_calls.load(Ordering::Relaxed),
+            decrypt_calls: self.decrypt_calls.load(Ordering::Relaxed),
+            padding_calls: self.padding_calls.load(Ordering::Relaxed),
+            verify_calls: self.verify_calls.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            bytes_received: self.bytes_received.load(Ordering::Relaxed),
+        }
+    }
+
+    pub fn reset(&self) {
+        self.queries.store(0, Ordering::Relaxed);
+        self.encrypt_calls.store(0, Ordering::Relaxed);
+        self.decrypt_calls.store(0, Ordering::Relaxed);
+        self.padding_calls.store(0, Ordering::Relaxed);
+        self.verify_calls.store(0, Ordering::Relaxed);
+        self.bytes_sent.store(0, Ordering::Relaxed);
+        self.bytes_received.store(0, Ordering::Relaxed);
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> O {
+        self.inner
+    }
+
+    fn count<T, F>(&self, calls: &AtomicUsize, data: &[u8], query: F) -> Result<T>
+    where
+        F: FnOnce(&[u8]) -> Result<T>,
+    {
+        // Reserve the query in a single atomic step, so concurrent callers cannot all pass the
+        // check and overshoot the budget
+        let budget = self.budget.unwrap_or(usize::MAX);
+        self.queries
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |q| {
+                (q < budget).then_some(q + 1)
+            })
+            .map_err(|_| BudgetExceeded { budget })?;
+
+        calls.fetch_add(1, Ordering::Relaxed);
+        self.bytes_sent.fetch_add(data.len(), Ordering::Relaxed);
+
+        query(data)
+    }
+
+    fn count_bytes(&self, out: Vec<u8>) -> Vec<u8> {
+        self.bytes_received.fetch_add(out.len(), Ordering::Relaxed);
+        out
+    }
+}
+
+impl<O: EncryptionOracle> EncryptionOracle for CountingOracle<O> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let out = self.count(&self.encrypt_calls, data, |d| self.inner.encrypt(d))?;
+        Ok(self.count_bytes(out))
+    }
+}
+
+impl<O: DecryptionOracle> DecryptionOracle for CountingOracle<O> {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let out = self.count(&self.decrypt_calls, data, |d| self.inner.decrypt(d))?;
+        Ok(self.count_bytes(out))
+    }
+}
+
+impl<O: PaddingOracle> PaddingOracle for CountingOracle<O> {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        self.count(&self.padding_calls, cipher, |c| {
+            self.inner.check_cipher_padding(c)
+        })
+    }
+}
+
+impl<O: VerificationOracle> VerificationOracle for CountingOracle<O> {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        self.count(&self.verify_calls, cipher, |c| self.inner.verify(c))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_and_budget() -> Result<()> {
+        let oracle = CountingOracle::with_budget(crate::OracleChallenge12::with_seed(32)?, 3);
+
+        oracle.encrypt(&[])?;
+        oracle.encrypt(&[0; 16])?;
+        oracle.encrypt(&[0; 4])?;
+
+        let stats = oracle.stats();
+        assert_eq!(stats.encrypt_calls, 3);
+        assert_eq!(stats.bytes_sent, 20);
+        assert_eq!(stats.bytes_received, 144 + 160 + 144);
+
+        let err = oracle.encrypt(&[]).unwrap_err();
+        assert!(err.downcast_ref::<BudgetExceeded>().is_some());
+        assert_eq!(oracle.stats().queries(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_budget_under_concurrency() -> Result<()> {
+        let oracle = CountingOracle::with_budget(crate::OracleChallenge12::with_seed(32)?, 25);
+
+        let answered: usize = std::thread::scope(|s| {
+            let workers: Vec<_> = (0..8)
+                .map(|_| s.spawn(|| (0..10).filter(|_| oracle.encrypt(&[]).is_ok()).count()))
+                .collect();
+            workers.into_iter().map(|w| w.join().unwrap()).sum()
+        });
+
+        assert_eq!(answered, 25);
+        assert_eq!(oracle.stats().encrypt_calls, 25);
+
+        Ok(())
+    }
+}
@@ -3,126 +3,260 @@ extern crate base64;
 extern crate hex;
 
 use aes::padding_pkcs7;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
 use std::fmt;
+use std::sync::Mutex;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
-pub trait Oracle {
+#[cfg(feature = "async")]
+mod asynchronous;
+mod builder;
+mod counting;
+mod noisy;
+mod remote;
+mod seed;
+mod transcript;
+mod wire;
+
+#[cfg(feature = "async")]
+pub use asynchronous::{AsyncEncryptionOracle, AsyncRemoteOracle, Blocking};
+use builder::Affix;
+pub use builder::{InputFilter, IvPolicy, OracleBuilder, Padding};
+pub use counting::{BudgetExceeded, CountingOracle, QueryStats};
+pub use noisy::{NoisyOracle, RateLimited, RateLimitedOracle, TransientError};
+pub use remote::{OracleServer, RemoteOracle};
+pub use seed::{report_seed_on_panic, rng, run_seed, seeded_rng, OracleRng, SEED_ENV};
+pub use transcript::{Query, RecordingOracle, ReplayOracle, UnseenQuery};
+
+/// Encrypts attacker controlled data, usually wrapped with some secret prefix and suffix.
+pub trait EncryptionOracle {
     fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>>;
-    fn decrypt(&self, _data: &[u8]) -> Result<Vec<u8>> {
-        Ok(Vec::new())
+}
+
+/// Decrypts attacker controlled ciphertexts and gives back the plaintext.
+pub trait DecryptionOracle {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Only tells whether a ciphertext decrypts to correctly padded plaintext.
+pub trait PaddingOracle {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool>;
+}
+
+/// Only tells whether a ciphertext decrypts to something the target accepts, e.g. an admin
+/// profile.
+pub trait VerificationOracle {
+    fn verify(&self, cipher: &[u8]) -> Result<bool>;
+}
+
+/// Rewrites part of a stream cipher ciphertext in place: the plaintext from `offset` is replaced
+/// by `newtext` and the whole ciphertext is returned.
+pub trait EditOracle {
+    fn edit(&self, cipher: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>>;
+}
+
+/// Any closure mapping input to ciphertext is an encryption oracle, so ad-hoc targets (even ones
+/// shelling out to other tools) can be attacked without writing a new type.
+impl<F> EncryptionOracle for F
+where
+    F: Fn(&[u8]) -> Result<Vec<u8>>,
+{
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self(data)
+    }
+}
+
+// A generic Box<T> impl would overlap with the closure impl above, since a boxed closure is a
+// closure too, so the boxed trait objects get their own impls.
+impl<'a> EncryptionOracle for Box<dyn EncryptionOracle + 'a> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        (**self).encrypt(data)
     }
 }
 
+impl<'a> EncryptionOracle for Box<dyn EncryptionOracle + Send + Sync + 'a> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        (**self).encrypt(data)
+    }
+}
+
+impl<T: DecryptionOracle + ?Sized> DecryptionOracle for Box<T> {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        (**self).decrypt(data)
+    }
+}
+
+impl<T: PaddingOracle + ?Sized> PaddingOracle for Box<T> {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        (**self).check_cipher_padding(cipher)
+    }
+}
+
+impl<T: VerificationOracle + ?Sized> VerificationOracle for Box<T> {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        (**self).verify(cipher)
+    }
+}
+
+impl<T: EditOracle + ?Sized> EditOracle for Box<T> {
+    fn edit(&self, cipher: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        (**self).edit(cipher, offset, newtext)
+    }
+}
+
+/// Returned by an `AesOracle` rejecting high ASCII when a decrypted plaintext holds some. Like
+/// many real error messages, it gives the plaintext away.
+#[derive(Debug)]
+pub struct InvalidAscii {
+    pub plaintext: Vec<u8>,
+}
+
+impl fmt::Display for InvalidAscii {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid ASCII in plaintext {}",
+            hex::vec_u8_to_string(self.plaintext.clone())
+        )
+    }
+}
+
+impl std::error::Error for InvalidAscii {}
+
+/// General AES oracle, configured through `OracleBuilder`. It encrypts the attacker input
+/// wrapped between its prefix and suffix.
 pub struct AesOracle {
     pub mode: aes::MODE,
-    key: [u8; 16],
+    key: Vec<u8>,
+    iv_policy: IvPolicy,
     iv: Option<[u8; 16]>,
-    suffix: Option<Vec<u8>>,
-    prefix: Option<Vec<u8>>,
+    nonce: u64,
+    reject_high_ascii: bool,
+    prefix: Affix,
+    suffix: Vec<u8>,
+    padding: Padding,
+    filters: Vec<InputFilter>,
+    rng: Mutex<OracleRng>,
 }
 
 impl AesOracle {
-    pub fn new(fixed_mode: Option<aes::MODE>) -> AesOracle {
-        let mut rng = rand::thread_rng();
-
-        let mode: aes::MODE;
-        let mut key = [0u8; 16];
-        let iv: Option<[u8; 16]>;
-
-        for i in 0..16 {
-            key[i] = rng.gen();
-        }
+    pub fn builder() -> OracleBuilder {
+        OracleBuilder::new()
+    }
 
-        // If given a mode, set it, otherwise randomly choose it.
-        if fixed_mode.is_none() {
-            match rng.gen_range(0..2) {
-                0u32 => mode = aes::MODE::ECB,
-                1u32 => mode = aes::MODE::CBC,
-                _ => panic!("Expected random value to be either 0 or 1."),
+    fn pad(&self, data: &mut Vec<u8>) -> Result<()> {
+        match self.padding {
+            Padding::Pkcs7 => padding_pkcs7(data, 16),
+            Padding::Zero => {
+                data.resize(data.len().div_ceil(16) * 16, 0);
+                Ok(())
             }
-        } else {
-            mode = fixed_mode.unwrap();
-        }
-
-        // If we are in CBC, generate a random 16 bytes IV
-        match mode {
-            aes::MODE::ECB => iv = None,
-            aes::MODE::CBC => {
-                let mut val = [0u8; 16];
-                for i in 0..16 {
-                    val[i] = rng.gen();
-                }
-                iv = Some(val);
+            Padding::None if !data.len().is_multiple_of(16) => {
+                Err(format!("Plaintext of {} bytes is not block aligned", data.len()).into())
             }
-        }
-
-        AesOracle {
-            key,
-            iv,
-            mode,
-            prefix: None,
-            suffix: None,
+            Padding::None => Ok(()),
         }
     }
 }
 
-impl Oracle for AesOracle {
+impl EncryptionOracle for AesOracle {
     fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        let mut true_data = Vec::new();
-        match &self.prefix {
-            Some(b) => {
-                true_data.extend(b);
-            }
-            None => {}
+        let mut input = data.to_vec();
+        for filter in &self.filters {
+            input = filter(&input)?;
         }
 
-        true_data.extend_from_slice(data);
+        let mut true_data = match &self.prefix {
+            Affix::Fixed(prefix) => prefix.clone(),
+            prefix => prefix.generate(&mut *self.rng.lock().unwrap_or_else(|e| e.into_inner())),
+        };
+        true_data.extend(input);
+        true_data.extend_from_slice(&self.suffix);
+        if self.mode != aes::MODE::CTR {
+            self.pad(&mut true_data)?;
+        }
 
-        match &self.suffix {
-            Some(b) => {
-                true_data.extend(b);
+        match (&self.mode, self.iv) {
+            (aes::MODE::CTR, _) => aes::encrypt_aes_ctr(&true_data, &self.key, self.nonce),
+            (aes::MODE::ECB, _) => aes::encrypt_aes_ecb_no_padding(&true_data, &self.key),
+            (aes::MODE::CBC, Some(iv)) => {
+                aes::encrypt_aes_cbc_no_padding(&true_data, &self.key, &iv)
+            }
+            (aes::MODE::CBC, None) => {
+                let iv: [u8; 16] = self.rng.lock().unwrap_or_else(|e| e.into_inner()).gen();
+                let mut cipher = iv.to_vec();
+                cipher.extend(aes::encrypt_aes_cbc_no_padding(&true_data, &self.key, &iv)?);
+                Ok(cipher)
             }
-            None => {}
         }
+    }
+}
 
-        match self.mode {
-            aes::MODE::ECB => return aes::encrypt_aes_128_ecb(&true_data, &self.key),
-            aes::MODE::CBC => {
-                return aes::encrypt_aes_128_cbc(&true_data, &self.key, &self.iv.unwrap())
+impl DecryptionOracle for AesOracle {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let plaintext = match (&self.mode, self.iv) {
+            (aes::MODE::CTR, _) => aes::decrypt_aes_ctr(data, &self.key, self.nonce),
+            (aes::MODE::ECB, _) => aes::decrypt_aes_ecb(data, &self.key),
+            (aes::MODE::CBC, Some(iv)) => aes::decrypt_aes_cbc(data, &self.key, &iv),
+            (aes::MODE::CBC, None) if data.len() < 16 => {
+                Err("Cipher is too short to hold its IV".into())
             }
+            (aes::MODE::CBC, None) => aes::decrypt_aes_cbc(&data[16..], &self.key, &data[..16]),
+        }?;
+
+        if self.reject_high_ascii && !plaintext.is_ascii() {
+            return Err(Box::new(InvalidAscii { plaintext }));
         }
+
+        Ok(plaintext)
     }
+}
 
-    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        match self.mode {
-            aes::MODE::ECB => aes::decrypt_aes_128_ecb(data, &self.key),
-            aes::MODE::CBC => aes::decrypt_aes_128_cbc(data, &self.key, &self.iv.unwrap()),
+impl EditOracle for AesOracle {
+    fn edit(&self, cipher: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        if self.mode != aes::MODE::CTR {
+            return Err(format!("Cannot edit a {} cipher in place", self.mode).into());
+        }
+        if offset + newtext.len() > cipher.len() {
+            return Err(format!(
+                "Edit of {} bytes at offset {} goes past the end of the {} bytes cipher",
+                newtext.len(),
+                offset,
+                cipher.len()
+            )
+            .into());
         }
+
+        let mut plaintext = self.decrypt(cipher)?;
+        plaintext[offset..offset + newtext.len()].copy_from_slice(newtext);
+
+        aes::encrypt_aes_ctr(&plaintext, &self.key, self.nonce)
     }
 }
 
 impl fmt::Display for AesOracle {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self.mode {
-            aes::MODE::ECB => write!(
-                f,
-                "KEY: {}\nMODE: {}",
-                hex::vec_u8_to_string(self.key.to_vec()),
-                self.mode,
-            ),
-            aes::MODE::CBC => write!(
-                f,
-                "KEY: {}\nIV: {}\nMODE: {}",
-                hex::vec_u8_to_string(self.key.to_vec()),
-                hex::vec_u8_to_string(self.iv.unwrap().to_vec()),
-                self.mode,
-            ),
+        writeln!(f, "KEY: {}", hex::vec_u8_to_string(self.key.clone()))?;
+        if self.mode == aes::MODE::CBC {
+            match (self.iv_policy, self.iv) {
+                (IvPolicy::Key, _) => writeln!(f, "IV: key")?,
+                (_, Some(iv)) => writeln!(f, "IV: {}", hex::vec_u8_to_string(iv.to_vec()))?,
+                (_, None) => writeln!(f, "IV: random per call")?,
+            }
+        }
+        if self.mode == aes::MODE::CTR {
+            writeln!(f, "NONCE: {}", self.nonce)?;
         }
+        write!(f, "MODE: {}", self.mode)
     }
 }
 
+const CHALL12_SUFFIX: &str = "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkg\
+aGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBq\
+dXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUg\
+YnkK";
+
 /// Suffix only oracle
 pub struct OracleChallenge12 {
     aes_oracle: AesOracle,
@@ -130,21 +264,25 @@ pub struct OracleChallenge12 {
 
 impl OracleChallenge12 {
     pub fn new() -> Result<Self> {
-        let suffix = base64::string_to_vec_u8(
-            "Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkg\
-aGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBq\
-dXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUg\
-YnkK",
-        )?;
+        Self::from_rng(rng()?)
+    }
 
-        let mut aes_oracle = AesOracle::new(Some(aes::MODE::ECB));
-        aes_oracle.suffix = Some(suffix);
+    pub fn with_seed(seed: u64) -> Result<Self> {
+        Self::from_rng(seeded_rng(seed))
+    }
+
+    fn from_rng(rng: OracleRng) -> Result<Self> {
+        let aes_oracle = AesOracle::builder()
+            .rng(rng)
+            .mode(aes::MODE::ECB)
+            .suffix(base64::string_to_vec_u8(CHALL12_SUFFIX)?)
+            .build()?;
 
         Ok(OracleChallenge12 { aes_oracle })
     }
 }
 
-impl Oracle for OracleChallenge12 {
+impl EncryptionOracle for OracleChallenge12 {
     fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
         self.aes_oracle.encrypt(data)
     }
@@ -158,38 +296,54 @@ pub struct OracleChallenge13 {
 
 impl OracleChallenge13 {
     pub fn new() -> Result<Self> {
-        // Generate the prefix
-        let prefix = Some(b"email=".to_vec());
+        Self::from_rng(rng()?)
+    }
 
-        // Generate the suffix
-        let mut rng = rand::thread_rng();
-        let uid = rng.gen::<u32>();
-        let suffix = Some(format!("&uid={}&role=user", uid).as_bytes().to_vec());
+    pub fn with_seed(seed: u64) -> Result<Self> {
+        Self::from_rng(seeded_rng(seed))
+    }
 
-        // Generate the AES Oracle
-        let mut aes_oracle = AesOracle::new(Some(aes::MODE::ECB));
+    fn from_rng(mut rng: OracleRng) -> Result<Self> {
+        let uid = rng.gen::<u32>();
 
-        // Set the prefix and suffix
-        aes_oracle.prefix = prefix;
-        aes_oracle.suffix = suffix;
+        let aes_oracle = AesOracle::builder()
+            .rng(rng)
+            .mode(aes::MODE::ECB)
+            .prefix(b"email=".to_vec())
+            .suffix(format!("&uid={}&role=user", uid).into_bytes())
+            // The email must be ascii and not contain cheating characters '&' or '='
+            .filter(|data| {
+                if data
+                    .iter()
+                    .any(|&c| !c.is_ascii() || c == b'&' || c == b'=')
+                {
+                    return Err("Invalid input".into());
+                }
+                Ok(data.to_vec())
+            })
+            .build()?;
 
         Ok(OracleChallenge13 { aes_oracle })
     }
+}
+
+impl VerificationOracle for OracleChallenge13 {
+    /// Accepts profiles that decrypt to a `role=admin` pair.
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        let mut clear = self.aes_oracle.decrypt(cipher)?;
+        if !aes::padding_is_valid(&clear, 16) {
+            return Ok(false);
+        }
+        aes::unpad_pkcs7(&mut clear, 16)?;
 
-    pub fn verify(&self, _: &[u8]) -> Result<bool> {
-        Ok(true)
+        Ok(clear
+            .split(|&c| c == b'&')
+            .any(|pair| pair == b"role=admin"))
     }
 }
 
-impl Oracle for OracleChallenge13 {
+impl EncryptionOracle for OracleChallenge13 {
     fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
-        // Check that data (email) is ascii and does not contain cheating characters '&' or '='
-        if data
-            .iter()
-            .any(|&c| !c.is_ascii() || c == b'&' || c == b'=')
-        {
-            panic!("Invalid input");
-        }
         self.aes_oracle.encrypt(data)
     }
 }
@@ -200,36 +354,26 @@ pub struct OracleChallenge14 {
 
 impl OracleChallenge14 {
     pub fn new() -> Result<Self> {
-        let mut rng = rand::thread_rng();
-
-        let mode = aes::MODE::ECB;
-
-        let mut key = [0u8; 16];
-        for i in 0..16 {
-            key[i] = rng.gen();
-        }
+        Self::from_rng(rng()?)
+    }
 
-        let suffix = base64::string_to_vec_u8("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK")?;
+    pub fn with_seed(seed: u64) -> Result<Self> {
+        Self::from_rng(seeded_rng(seed))
+    }
 
-        let prefix_len = rng.gen_range(5..25);
-        let mut prefix = vec![0u8; prefix_len];
-        for i in 0..prefix_len {
-            prefix[i] = rng.gen();
-        }
+    fn from_rng(rng: OracleRng) -> Result<Self> {
+        let aes_oracle = AesOracle::builder()
+            .rng(rng)
+            .mode(aes::MODE::ECB)
+            .random_prefix(5..25)
+            .suffix(base64::string_to_vec_u8(CHALL12_SUFFIX)?)
+            .build()?;
 
-        Ok(OracleChallenge14 {
-            aes_oracle: AesOracle {
-                mode,
-                key,
-                iv: None,
-                prefix: Some(prefix),
-                suffix: Some(suffix),
-            },
-        })
+        Ok(OracleChallenge14 { aes_oracle })
     }
 }
 
-impl Oracle for OracleChallenge14 {
+impl EncryptionOracle for OracleChallenge14 {
     fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
         self.aes_oracle.encrypt(data)
     }
@@ -250,34 +394,143 @@ const CHALL17_STRINGS: [&str; 10] = [
     "MDAwMDA5aXRoIG15IHJhZy10b3AgZG93biBzbyBteSBoYWlyIGNhbiBibG93",
 ];
 
+/// Each encryption picks one of the ten strings at random and returns it CBC encrypted under a
+/// fresh IV, sent as the first block. Ciphertexts checked for padding carry their IV the same way.
 pub struct OracleChallenge17 {
     aes_oracle: AesOracle,
+    rng: Mutex<OracleRng>,
 }
 
 impl OracleChallenge17 {
     pub fn new() -> Result<Self> {
-        let mut aes_oracle = AesOracle::new(Some(aes::MODE::CBC));
+        Self::from_rng(rng()?)
+    }
 
-        let mut rng = rand::thread_rng();
+    pub fn with_seed(seed: u64) -> Result<Self> {
+        Self::from_rng(seeded_rng(seed))
+    }
 
-        let i = rng.gen_range(0..10);
-        let mut s = base64::string_to_vec_u8(CHALL17_STRINGS[i])?;
-        padding_pkcs7(&mut s, 16)?;
+    fn from_rng(mut rng: OracleRng) -> Result<Self> {
+        let aes_oracle = AesOracle::builder()
+            .rng(OracleRng::from_seed(rng.gen()))
+            .mode(aes::MODE::CBC)
+            .iv_policy(IvPolicy::RandomPerCall)
+            .build()?;
 
-        aes_oracle.suffix = Some(s);
+        Ok(OracleChallenge17 {
+            aes_oracle,
+            rng: Mutex::new(rng),
+        })
+    }
 
-        Ok(OracleChallenge17 { aes_oracle })
+    /// Not part of the challenge, the attacker only gets padding checks. Lets tests verify what
+    /// forged ciphertexts decrypt to, without handing attacks a `DecryptionOracle`.
+    #[doc(hidden)]
+    pub fn reveal_plaintext(&self, cipher: &[u8]) -> Result<Vec<u8>> {
+        self.aes_oracle.decrypt(cipher)
     }
+}
 
-    pub fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+impl PaddingOracle for OracleChallenge17 {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        if !cipher.len().is_multiple_of(16) || cipher.len() < 32 {
+            return Err(format!(
+                "Cipher should be an IV and at least one block, is {} bytes",
+                cipher.len()
+            )
+            .into());
+        }
         let clear = self.aes_oracle.decrypt(cipher)?;
 
         Ok(aes::padding_is_valid(&clear, 16))
     }
 }
 
-impl Oracle for OracleChallenge17 {
+impl EncryptionOracle for OracleChallenge17 {
     fn encrypt(&self, _: &[u8]) -> Result<Vec<u8>> {
-        self.aes_oracle.encrypt(&[])
+        let i = self
+            .rng
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .gen_range(0..CHALL17_STRINGS.len());
+
+        self.aes_oracle
+            .encrypt(&base64::string_to_vec_u8(CHALL17_STRINGS[i])?)
+    }
+}
+
+/*               SET 4                   */
+
+/// Oracle of challenge 25: holds a plaintext CTR encrypted under a random key and nonce, and
+/// lets anyone edit that ciphertext.
+pub struct OracleChallenge25 {
+    aes_oracle: AesOracle,
+    cipher: Vec<u8>,
+}
+
+impl OracleChallenge25 {
+    pub fn new(plaintext: &[u8]) -> Result<Self> {
+        Self::from_rng(rng()?, plaintext)
+    }
+
+    pub fn with_seed(seed: u64, plaintext: &[u8]) -> Result<Self> {
+        Self::from_rng(seeded_rng(seed), plaintext)
+    }
+
+    fn from_rng(rng: OracleRng, plaintext: &[u8]) -> Result<Self> {
+        let aes_oracle = AesOracle::builder().rng(rng).mode(aes::MODE::CTR).build()?;
+        let cipher = aes_oracle.encrypt(plaintext)?;
+
+        Ok(OracleChallenge25 { aes_oracle, cipher })
+    }
+
+    /// The ciphertext to recover.
+    pub fn cipher(&self) -> &[u8] {
+        &self.cipher
+    }
+}
+
+impl EditOracle for OracleChallenge25 {
+    fn edit(&self, cipher: &[u8], offset: usize, newtext: &[u8]) -> Result<Vec<u8>> {
+        self.aes_oracle.edit(cipher, offset, newtext)
+    }
+}
+
+/// Oracle of challenge 27: CBC encrypts the attacker input with the key as IV, and decrypts
+/// ciphertexts, rejecting the plaintexts holding high ASCII.
+pub struct OracleChallenge27 {
+    aes_oracle: AesOracle,
+}
+
+impl OracleChallenge27 {
+    pub fn new() -> Result<Self> {
+        Self::from_rng(rng()?)
+    }
+
+    pub fn with_seed(seed: u64) -> Result<Self> {
+        Self::from_rng(seeded_rng(seed))
+    }
+
+    fn from_rng(rng: OracleRng) -> Result<Self> {
+        let aes_oracle = AesOracle::builder()
+            .rng(rng)
+            .mode(aes::MODE::CBC)
+            .iv_policy(IvPolicy::Key)
+            .reject_high_ascii()
+            .build()?;
+
+        Ok(OracleChallenge27 { aes_oracle })
+    }
+}
+
+impl EncryptionOracle for OracleChallenge27 {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.aes_oracle.encrypt(data)
+    }
+}
+
+impl DecryptionOracle for OracleChallenge27 {
+    fn decrypt(&self, cipher: &[u8]) -> Result<Vec<u8>> {
+        self.aes_oracle.decrypt(cipher)
     }
 }
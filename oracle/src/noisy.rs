@@ -0,0 +1,260 @@
+use crate::{
+    DecryptionOracle, EncryptionOracle, OracleRng, PaddingOracle, Result, VerificationOracle,
+};
+use rand::Rng;
+use std::error::Error;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Injected by a `NoisyOracle` in place of an answer, retrying the query may succeed.
+#[derive(Debug)]
+pub struct TransientError;
+
+impl fmt::Display for TransientError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Transient oracle error, try again")
+    }
+}
+
+impl Error for TransientError {}
+
+/// Returned by a `RateLimitedOracle` out of tokens, a token is available after `retry_after`.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: Duration,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Oracle rate limit hit, retry after {:?}",
+            self.retry_after
+        )
+    }
+}
+
+impl Error for RateLimited {}
+
+/// Wraps an oracle to fail a share of the queries with `TransientError` and delay answers by a
+/// random latency, like a real target would. It offers the same capabilities as the wrapped
+/// oracle.
+pub struct NoisyOracle<O> {
+    inner: O,
+    error_rate: f64,
+    latency: Range<Duration>,
+    rng: Mutex<OracleRng>,
+}
+
+impl<O> NoisyOracle<O> {
+    /// Starts without errors nor latency.
+    pub fn new(inner: O) -> Result<Self> {
+        Ok(NoisyOracle {
+            inner,
+            error_rate: 0.0,
+            latency: Duration::ZERO..Duration::ZERO,
+            rng: Mutex::new(crate::rng()?),
+        })
+    }
+
+    /// Share of the queries failing, between 0 and 1.
+    ///
+    /// # Panics
+    ///
+    /// If `error_rate` is NaN or outside of [0, 1].
+    pub fn error_rate(mut self, error_rate: f64) -> Self {
+        assert!(
+            (0.0..=1.0).contains(&error_rate),
+            "Error rate should be between 0 and 1, is {}",
+            error_rate
+        );
+        self.error_rate = error_rate;
+        self
+    }
+
+    /// Each answer is delayed by a duration drawn from `latency`.
+    pub fn latency(mut self, latency: Range<Duration>) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        NoisyOracle {
+            rng: Mutex::new(crate::seeded_rng(seed)),
+            ..self
+        }
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    fn disturb<T>(&self, query: impl FnOnce(&O) -> Result<T>) -> Result<T> {
+        let (delay, fail) = {
+            let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+            let delay = match self.latency.is_empty() {
+                true => self.latency.start,
+                false => rng.gen_range(self.latency.clone()),
+            };
+            (delay, rng.gen_bool(self.error_rate))
+        };
+
+        thread::sleep(delay);
+        match fail {
+            true => Err(Box::new(TransientError)),
+            false => query(&self.inner),
+        }
+    }
+}
+
+impl<O: EncryptionOracle> EncryptionOracle for NoisyOracle<O> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.disturb(|o| o.encrypt(data))
+    }
+}
+
+impl<O: DecryptionOracle> DecryptionOracle for NoisyOracle<O> {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.disturb(|o| o.decrypt(data))
+    }
+}
+
+impl<O: PaddingOracle> PaddingOracle for NoisyOracle<O> {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        self.disturb(|o| o.check_cipher_padding(cipher))
+    }
+}
+
+impl<O: VerificationOracle> VerificationOracle for NoisyOracle<O> {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        self.disturb(|o| o.verify(cipher))
+    }
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled: Instant,
+}
+
+/// Wraps an oracle behind a token bucket: a burst of `capacity` queries, then `rate` queries per
+/// second. Queries over the limit fail with `RateLimited`. It offers the same capabilities as the
+/// wrapped oracle.
+pub struct RateLimitedOracle<O> {
+    inner: O,
+    capacity: f64,
+    rate: f64,
+    bucket: Mutex<Bucket>,
+}
+
+impl<O> RateLimitedOracle<O> {
+    /// # Panics
+    ///
+    /// If `rate` is not a finite, positive number of queries per second.
+    pub fn new(inner: O, capacity: usize, rate: f64) -> Self {
+        assert!(
+            rate.is_finite() && rate > 0.0,
+            "Rate should be a positive number of queries per second, is {}",
+            rate
+        );
+
+        RateLimitedOracle {
+            inner,
+            capacity: capacity as f64,
+            rate,
+            bucket: Mutex::new(Bucket {
+                tokens: capacity as f64,
+                refilled: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    fn take_token(&self) -> Result<()> {
+        let mut bucket = self.bucket.lock().unwrap_or_else(|e| e.into_inner());
+
+        let now = Instant::now();
+        let elapsed = now.duration_since(bucket.refilled).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+        bucket.refilled = now;
+
+        if bucket.tokens < 1.0 {
+            return Err(Box::new(RateLimited {
+                retry_after: Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate),
+            }));
+        }
+        bucket.tokens -= 1.0;
+
+        Ok(())
+    }
+}
+
+impl<O: EncryptionOracle> EncryptionOracle for RateLimitedOracle<O> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.take_token()?;
+        self.inner.encrypt(data)
+    }
+}
+
+impl<O: DecryptionOracle> DecryptionOracle for RateLimitedOracle<O> {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.take_token()?;
+        self.inner.decrypt(data)
+    }
+}
+
+impl<O: PaddingOracle> PaddingOracle for RateLimitedOracle<O> {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        self.take_token()?;
+        self.inner.check_cipher_padding(cipher)
+    }
+}
+
+impl<O: VerificationOracle> VerificationOracle for RateLimitedOracle<O> {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        self.take_token()?;
+        self.inner.verify(cipher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::OracleChallenge12;
+
+    #[test]
+    fn test_noise_and_rate_limit() -> Result<()> {
+        let noisy = NoisyOracle::new(OracleChallenge12::with_seed(39)?)?
+            .error_rate(0.5)
+            .seed(39);
+        let failures = (0..200).filter(|_| noisy.encrypt(b"").is_err()).count();
+        assert!((60..140).contains(&failures), "{} failures", failures);
+
+        let limited = RateLimitedOracle::new(OracleChallenge12::with_seed(39)?, 2, 10.0);
+        limited.encrypt(b"")?;
+        limited.encrypt(b"")?;
+        let err = limited.encrypt(b"").unwrap_err();
+        let retry_after = err.downcast_ref::<RateLimited>().unwrap().retry_after;
+        thread::sleep(retry_after + Duration::from_millis(5));
+        limited.encrypt(b"")?;
+
+        Ok(())
+    }
+
+    #[test]
+    #[should_panic(expected = "Error rate")]
+    fn test_error_rate_out_of_range() {
+        let _ = NoisyOracle::new(()).unwrap().error_rate(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rate should be")]
+    fn test_zero_rate() {
+        let _ = RateLimitedOracle::new((), 1, 0.0);
+    }
+}
@@ -0,0 +1,319 @@
+//! Line based protocol exposing oracles over TCP. Each request is one line made of a command
+//! (`ENCRYPT`, `DECRYPT`, `PADDING` or `VERIFY`), a space and the hex encoded input. Each
+//! response is one line, either `OK ` followed by hex encoded bytes or `true`/`false`, or `ERR `
+//! followed by the error as a JSON object holding its kind, so that clients can rebuild errors
+//! such as `TransientError` or `RateLimited`.
+
+use crate::wire::WireError;
+use crate::{DecryptionOracle, EncryptionOracle, PaddingOracle, Result, VerificationOracle};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+type Shared<T> = Option<Arc<T>>;
+
+/// Serves the capabilities it was given over the line based TCP protocol, one thread per
+/// connection.
+#[derive(Default)]
+pub struct OracleServer {
+    encryption: Shared<dyn EncryptionOracle + Send + Sync>,
+    decryption: Shared<dyn DecryptionOracle + Send + Sync>,
+    padding: Shared<dyn PaddingOracle + Send + Sync>,
+    verification: Shared<dyn VerificationOracle + Send + Sync>,
+}
+
+impl OracleServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn encryption<O: EncryptionOracle + Send + Sync + 'static>(mut self, o: Arc<O>) -> Self {
+        self.encryption = Some(o);
+        self
+    }
+
+    pub fn decryption<O: DecryptionOracle + Send + Sync + 'static>(mut self, o: Arc<O>) -> Self {
+        self.decryption = Some(o);
+        self
+    }
+
+    pub fn padding<O: PaddingOracle + Send + Sync + 'static>(mut self, o: Arc<O>) -> Self {
+        self.padding = Some(o);
+        self
+    }
+
+    pub fn verification<O>(mut self, o: Arc<O>) -> Self
+    where
+        O: VerificationOracle + Send + Sync + 'static,
+    {
+        self.verification = Some(o);
+        self
+    }
+
+    /// Accepts connections on `listener` forever.
+    pub fn serve(self, listener: TcpListener) -> Result<()> {
+        let server = Arc::new(self);
+
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = server.clone();
+            thread::spawn(move || {
+                // A client hanging up is not an error of the server
+                let _ = server.handle(stream);
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Binds `addr` and serves from a background thread, returning the bound address.
+    pub fn spawn<A: ToSocketAddrs>(self, addr: A) -> Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)?;
+        let local_addr = listener.local_addr()?;
+
+        thread::spawn(move || self.serve(listener));
+
+        Ok(local_addr)
+    }
+
+    fn handle(&self, stream: TcpStream) -> Result<()> {
+        let mut writer = stream.try_clone()?;
+
+        for line in BufReader::new(stream).lines() {
+            let response = match self.answer(&line?) {
+                Ok(answer) => format!("OK {}", answer),
+                Err(e) => format!(
+                    "ERR {}",
+                    serde_json::to_string(&WireError::from_error(e.as_ref()))?
+                ),
+            };
+            writeln!(writer, "{}", response)?;
+        }
+
+        Ok(())
+    }
+
+    fn answer(&self, line: &str) -> Result<String> {
+        let (command, data) = line
+            .trim_end()
+            .split_once(' ')
+            .unwrap_or((line.trim_end(), ""));
+        let data = hex::string_to_vec_u8(data)?;
+        let missing = || format!("Oracle does not support {}", command);
+
+        match command {
+            "ENCRYPT" => match &self.encryption {
+                Some(o) => Ok(hex::vec_u8_to_string(o.encrypt(&data)?)),
+                None => Err(missing().into()),
+            },
+            "DECRYPT" => match &self.decryption {
+                Some(o) => Ok(hex::vec_u8_to_string(o.decrypt(&data)?)),
+                None => Err(missing().into()),
+            },
+            "PADDING" => match &self.padding {
+                Some(o) => Ok(o.check_cipher_padding(&data)?.to_string()),
+                None => Err(missing().into()),
+            },
+            "VERIFY" => match &self.verification {
+                Some(o) => Ok(o.verify(&data)?.to_string()),
+                None => Err(missing().into()),
+            },
+            _ => Err(format!("Unknown command {:?}", command).into()),
+        }
+    }
+}
+
+/// Reads one response line of the protocol, turning `ERR` lines back into typed errors.
+pub(crate) fn parse_response(line: &str) -> Result<String> {
+    let line = line.trim_end();
+    if let Some(answer) = line.strip_prefix("OK") {
+        Ok(answer.trim_start().to_string())
+    } else if let Some(error) = line.strip_prefix("ERR ") {
+        Err(serde_json::from_str::<WireError>(error)?.into_error())
+    } else {
+        Err(format!("Malformed oracle server response {:?}", line).into())
+    }
+}
+
+struct Connection {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+}
+
+impl Connection {
+    fn open(addr: SocketAddr, timeout: Option<Duration>) -> Result<Self> {
+        Self::new(TcpStream::connect(addr)?, timeout)
+    }
+
+    fn new(writer: TcpStream, timeout: Option<Duration>) -> Result<Self> {
+        writer.set_nodelay(true)?;
+        writer.set_read_timeout(timeout)?;
+
+        Ok(Connection {
+            reader: BufReader::new(writer.try_clone()?),
+            writer,
+        })
+    }
+
+    /// Sends one request and reads its response line.
+    fn exchange(&mut self, command: &str, data: &[u8]) -> Result<String> {
+        writeln!(
+            self.writer,
+            "{} {}",
+            command,
+            hex::vec_u8_to_string(data.to_vec())
+        )?;
+
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err("Oracle server closed the connection".into());
+        }
+
+        Ok(line)
+    }
+}
+
+struct State {
+    timeout: Option<Duration>,
+    connection: Option<Connection>,
+}
+
+/// Client of an `OracleServer`. Every capability is offered, queries the server does not
+/// support come back as errors.
+///
+/// A query failing on I/O, a timeout included, drops the connection: the response may still be
+/// on its way and would be read as the answer to the next query. The next query connects again.
+pub struct RemoteOracle {
+    addr: SocketAddr,
+    state: Mutex<State>,
+}
+
+impl RemoteOracle {
+    pub fn connect<A: ToSocketAddrs>(addr: A) -> Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+
+        Ok(RemoteOracle {
+            addr: stream.peer_addr()?,
+            state: Mutex::new(State {
+                timeout: None,
+                connection: Some(Connection::new(stream, None)?),
+            }),
+        })
+    }
+
+    /// Fails queries whose response takes longer than `timeout`.
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<()> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(connection) = &state.connection {
+            connection.writer.set_read_timeout(timeout)?;
+        }
+        state.timeout = timeout;
+
+        Ok(())
+    }
+
+    fn query(&self, command: &str, data: &[u8]) -> Result<String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let mut connection = match state.connection.take() {
+            Some(connection) => connection,
+            None => Connection::open(self.addr, state.timeout)?,
+        };
+        // On error the connection is dropped rather than put back
+        let line = connection.exchange(command, data)?;
+        state.connection = Some(connection);
+
+        parse_response(&line)
+    }
+
+    fn query_bool(&self, command: &str, data: &[u8]) -> Result<bool> {
+        Ok(self.query(command, data)?.parse()?)
+    }
+}
+
+impl EncryptionOracle for RemoteOracle {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        hex::string_to_vec_u8(&self.query("ENCRYPT", data)?)
+    }
+}
+
+impl DecryptionOracle for RemoteOracle {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        hex::string_to_vec_u8(&self.query("DECRYPT", data)?)
+    }
+}
+
+impl PaddingOracle for RemoteOracle {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        self.query_bool("PADDING", cipher)
+    }
+}
+
+impl VerificationOracle for RemoteOracle {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        self.query_bool("VERIFY", cipher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NoisyOracle, OracleChallenge17, TransientError};
+
+    #[test]
+    fn test_remote_matches_local() -> Result<()> {
+        let served = Arc::new(OracleChallenge17::with_seed(38)?);
+        let addr = OracleServer::new()
+            .encryption(served.clone())
+            .padding(served)
+            .spawn("127.0.0.1:0")?;
+
+        let local = OracleChallenge17::with_seed(38)?;
+        let remote = RemoteOracle::connect(addr)?;
+
+        let cipher = remote.encrypt(b"")?;
+        assert_eq!(cipher, local.encrypt(b"")?);
+        assert!(remote.check_cipher_padding(&cipher)?);
+        assert!(remote.decrypt(&cipher).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_remote_errors_keep_their_type() -> Result<()> {
+        let noisy = NoisyOracle::new(OracleChallenge17::with_seed(38)?)?.error_rate(1.0);
+        let addr = OracleServer::new()
+            .encryption(Arc::new(noisy))
+            .spawn("127.0.0.1:0")?;
+
+        let err = RemoteOracle::connect(addr)?.encrypt(b"").unwrap_err();
+        assert!(err.downcast_ref::<TransientError>().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeout_does_not_desync() -> Result<()> {
+        let echo = |data: &[u8]| -> Result<Vec<u8>> {
+            if data == b"slow" {
+                thread::sleep(Duration::from_millis(200));
+            }
+            Ok(data.to_vec())
+        };
+        let addr = OracleServer::new()
+            .encryption(Arc::new(echo))
+            .spawn("127.0.0.1:0")?;
+
+        let remote = RemoteOracle::connect(addr)?;
+        remote.set_timeout(Some(Duration::from_millis(50)))?;
+        assert!(remote.encrypt(b"slow").is_err());
+
+        // The late answer to the slow query must not be taken for this one
+        thread::sleep(Duration::from_millis(300));
+        assert_eq!(remote.encrypt(b"fast")?, b"fast");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use crate::Result;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::env;
+use std::sync::{Mutex, OnceLock};
+
+/// Environment variable fixing the seed of every oracle built during a run.
+pub const SEED_ENV: &str = "CRYPTOPALS_SEED";
+
+/// Random generator used by the oracles, so runs can be replayed from a seed.
+pub type OracleRng = ChaCha20Rng;
+
+static RUN_SEED: OnceLock<u64> = OnceLock::new();
+static RUN_RNG: OnceLock<Mutex<OracleRng>> = OnceLock::new();
+
+/// Seed of the current run: read from `CRYPTOPALS_SEED` if set, drawn at random otherwise.
+/// Fails if `CRYPTOPALS_SEED` is not a u64.
+pub fn run_seed() -> Result<u64> {
+    if let Some(seed) = RUN_SEED.get() {
+        return Ok(*seed);
+    }
+
+    let seed = match env::var(SEED_ENV) {
+        Ok(seed) => parse_seed(&seed)
+            .ok_or_else(|| format!("{} should be a u64, is {:?}", SEED_ENV, seed))?,
+        Err(_) => rand::thread_rng().gen(),
+    };
+
+    Ok(*RUN_SEED.get_or_init(|| seed))
+}
+
+fn parse_seed(seed: &str) -> Option<u64> {
+    seed.trim().parse().ok()
+}
+
+pub fn seeded_rng(seed: u64) -> OracleRng {
+    OracleRng::seed_from_u64(seed)
+}
+
+/// Next generator derived from the run seed. Oracles built in the same order get the same keys
+/// on every run with the same seed.
+pub fn rng() -> Result<OracleRng> {
+    let seed = run_seed()?;
+    let run_rng = RUN_RNG.get_or_init(|| Mutex::new(seeded_rng(seed)));
+    let mut run_rng = run_rng.lock().unwrap_or_else(|e| e.into_inner());
+
+    Ok(OracleRng::from_seed(run_rng.gen()))
+}
+
+/// Makes panics also print the run seed, so a failing attack can be replayed exactly.
+pub fn report_seed_on_panic() -> Result<()> {
+    let seed = run_seed()?;
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        eprintln!("Replay this run with {}={}", SEED_ENV, seed);
+    }));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_seed;
+    use crate::{EncryptionOracle, OracleChallenge14};
+
+    #[test]
+    fn test_parse_seed() {
+        assert_eq!(parse_seed(" 42\n"), Some(42));
+        assert_eq!(parse_seed("forty-two"), None);
+        assert_eq!(parse_seed("-1"), None);
+    }
+
+    #[test]
+    fn test_same_seed_same_oracle() -> crate::Result<()> {
+        let first = OracleChallenge14::with_seed(42)?;
+        let second = OracleChallenge14::with_seed(42)?;
+
+        assert_eq!(first.encrypt(b"replay")?, second.encrypt(b"replay")?);
+        assert_ne!(
+            first.encrypt(b"replay")?,
+            OracleChallenge14::with_seed(43)?.encrypt(b"replay")?
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,300 @@
+use crate::wire::WireError;
+use crate::{DecryptionOracle, EncryptionOracle, PaddingOracle, Result, VerificationOracle};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// Kind of oracle query held by a transcript line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Query {
+    Encrypt,
+    Decrypt,
+    Padding,
+    Verify,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Answer {
+    Bytes(String),
+    Valid(bool),
+    Error(WireError),
+}
+
+/// One JSON line of a transcript, bytes are hex encoded.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    query: Query,
+    input: String,
+    #[serde(flatten)]
+    answer: Answer,
+}
+
+/// Returned by a `ReplayOracle` asked a query its transcript does not hold.
+#[derive(Debug)]
+pub struct UnseenQuery {
+    pub query: Query,
+    pub input: Vec<u8>,
+}
+
+impl fmt::Display for UnseenQuery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "No {:?} query with input {} in the transcript",
+            self.query,
+            hex::vec_u8_to_string(self.input.clone())
+        )
+    }
+}
+
+impl Error for UnseenQuery {}
+
+/// Wraps an oracle and writes every query and its answer, errors included, as JSON Lines. Errors
+/// are recorded with their kind, so a replay gives back the same error types. It offers the same
+/// capabilities as the wrapped oracle.
+pub struct RecordingOracle<O, W: Write = BufWriter<File>> {
+    inner: O,
+    writer: Mutex<W>,
+}
+
+impl<O> RecordingOracle<O> {
+    /// Records to the file at `path`, replacing it.
+    pub fn create<P: AsRef<Path>>(inner: O, path: P) -> Result<Self> {
+        Ok(Self::new(inner, BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<O, W: Write> RecordingOracle<O, W> {
+    pub fn new(inner: O, writer: W) -> Self {
+        RecordingOracle {
+            inner,
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn inner(&self) -> &O {
+        &self.inner
+    }
+
+    fn record<T>(&self, query: Query, input: &[u8], result: Result<T>) -> Result<T>
+    where
+        T: Clone + Into<Answer>,
+    {
+        let answer = match &result {
+            Ok(output) => output.clone().into(),
+            Err(e) => Answer::Error(WireError::from_error(e.as_ref())),
+        };
+        let record = Record {
+            query,
+            input: hex::vec_u8_to_string(input.to_vec()),
+            answer,
+        };
+
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        serde_json::to_writer(&mut *writer, &record)?;
+        writeln!(writer)?;
+        writer.flush()?;
+
+        result
+    }
+}
+
+impl From<Vec<u8>> for Answer {
+    fn from(bytes: Vec<u8>) -> Self {
+        Answer::Bytes(hex::vec_u8_to_string(bytes))
+    }
+}
+
+impl From<bool> for Answer {
+    fn from(valid: bool) -> Self {
+        Answer::Valid(valid)
+    }
+}
+
+impl<O: EncryptionOracle, W: Write> EncryptionOracle for RecordingOracle<O, W> {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.record(Query::Encrypt, data, self.inner.encrypt(data))
+    }
+}
+
+impl<O: DecryptionOracle, W: Write> DecryptionOracle for RecordingOracle<O, W> {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.record(Query::Decrypt, data, self.inner.decrypt(data))
+    }
+}
+
+impl<O: PaddingOracle, W: Write> PaddingOracle for RecordingOracle<O, W> {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        self.record(
+            Query::Padding,
+            cipher,
+            self.inner.check_cipher_padding(cipher),
+        )
+    }
+}
+
+impl<O: VerificationOracle, W: Write> VerificationOracle for RecordingOracle<O, W> {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        self.record(Query::Verify, cipher, self.inner.verify(cipher))
+    }
+}
+
+/// Answers queries from a transcript written by a `RecordingOracle`, erroring with
+/// `UnseenQuery` on queries it does not hold. When a query was recorded several times, the first
+/// answer is served.
+pub struct ReplayOracle {
+    answers: HashMap<(Query, Vec<u8>), Answer>,
+}
+
+impl ReplayOracle {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Self> {
+        let mut answers = HashMap::new();
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: Record = serde_json::from_str(&line)?;
+            answers
+                .entry((record.query, hex::string_to_vec_u8(&record.input)?))
+                .or_insert(record.answer);
+        }
+
+        Ok(ReplayOracle { answers })
+    }
+
+    /// Number of distinct queries in the transcript.
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+
+    fn answer(&self, query: Query, input: &[u8]) -> Result<&Answer> {
+        match self.answers.get(&(query, input.to_vec())) {
+            Some(Answer::Error(e)) => Err(e.clone().into_error()),
+            Some(answer) => Ok(answer),
+            None => Err(Box::new(UnseenQuery {
+                query,
+                input: input.to_vec(),
+            })),
+        }
+    }
+
+    fn bytes(&self, query: Query, input: &[u8]) -> Result<Vec<u8>> {
+        match self.answer(query, input)? {
+            Answer::Bytes(bytes) => hex::string_to_vec_u8(bytes),
+            _ => Err(format!("Transcript answer to {:?} query is not bytes", query).into()),
+        }
+    }
+
+    fn valid(&self, query: Query, input: &[u8]) -> Result<bool> {
+        match self.answer(query, input)? {
+            Answer::Valid(valid) => Ok(*valid),
+            _ => Err(format!("Transcript answer to {:?} query is not a boolean", query).into()),
+        }
+    }
+}
+
+impl EncryptionOracle for ReplayOracle {
+    fn encrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.bytes(Query::Encrypt, data)
+    }
+}
+
+impl DecryptionOracle for ReplayOracle {
+    fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>> {
+        self.bytes(Query::Decrypt, data)
+    }
+}
+
+impl PaddingOracle for ReplayOracle {
+    fn check_cipher_padding(&self, cipher: &[u8]) -> Result<bool> {
+        self.valid(Query::Padding, cipher)
+    }
+}
+
+impl VerificationOracle for ReplayOracle {
+    fn verify(&self, cipher: &[u8]) -> Result<bool> {
+        self.valid(Query::Verify, cipher)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NoisyOracle, OracleChallenge13, OracleChallenge17, TransientError};
+
+    #[test]
+    fn test_record_then_replay() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("transcript-{}.jsonl", std::process::id()));
+
+        let recorder = RecordingOracle::create(OracleChallenge17::with_seed(17)?, &path)?;
+        let cipher = recorder.encrypt(b"")?;
+        let valid = recorder.check_cipher_padding(&cipher)?;
+        let forged = recorder.check_cipher_padding(&cipher[..32])?;
+        drop(recorder);
+
+        let replay = ReplayOracle::open(&path)?;
+        std::fs::remove_file(&path)?;
+        assert_eq!(replay.encrypt(b"")?, cipher);
+        assert_eq!(replay.check_cipher_padding(&cipher)?, valid);
+        assert_eq!(replay.check_cipher_padding(&cipher[..32])?, forged);
+
+        let err = replay.encrypt(b"unseen").unwrap_err();
+        assert!(err.downcast_ref::<UnseenQuery>().is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_recorded_errors() -> Result<()> {
+        let mut transcript = Vec::new();
+        let recorder = RecordingOracle::new(OracleChallenge13::with_seed(13)?, &mut transcript);
+        let err = recorder.encrypt(b"a&role=admin").unwrap_err();
+        drop(recorder);
+
+        let line = String::from_utf8(transcript.clone())?;
+        assert!(
+            line.starts_with(r#"{"query":"encrypt","input":"6126726f6c653d61646d696e","error":"#)
+        );
+
+        let replay = ReplayOracle::from_reader(&transcript[..])?;
+        assert_eq!(replay.len(), 1);
+        assert_eq!(
+            replay.encrypt(b"a&role=admin").unwrap_err().to_string(),
+            err.to_string()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_keeps_error_types() -> Result<()> {
+        let mut transcript = Vec::new();
+        let noisy = NoisyOracle::new(OracleChallenge17::with_seed(37)?)?.error_rate(1.0);
+        let recorder = RecordingOracle::new(noisy, &mut transcript);
+        assert!(recorder.encrypt(b"").is_err());
+        drop(recorder);
+
+        let replay = ReplayOracle::from_reader(&transcript[..])?;
+        let err = replay.encrypt(b"").unwrap_err();
+        assert!(err.downcast_ref::<TransientError>().is_some());
+
+        Ok(())
+    }
+}
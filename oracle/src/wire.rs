@@ -0,0 +1,52 @@
+use crate::{BudgetExceeded, InvalidAscii, RateLimited, TransientError};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::time::Duration;
+
+/// Oracle error as written to transcripts and sent over the wire. The errors attacks react to
+/// keep their type, so that they can still be downcast once read back; any other error only
+/// keeps its message.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum WireError {
+    Transient,
+    RateLimited { retry_after: Duration },
+    BudgetExceeded { budget: usize },
+    InvalidAscii { plaintext: String },
+    Other { message: String },
+}
+
+impl WireError {
+    pub(crate) fn from_error(e: &(dyn Error + Send + Sync + 'static)) -> Self {
+        if e.is::<TransientError>() {
+            WireError::Transient
+        } else if let Some(e) = e.downcast_ref::<RateLimited>() {
+            WireError::RateLimited {
+                retry_after: e.retry_after,
+            }
+        } else if let Some(e) = e.downcast_ref::<BudgetExceeded>() {
+            WireError::BudgetExceeded { budget: e.budget }
+        } else if let Some(e) = e.downcast_ref::<InvalidAscii>() {
+            WireError::InvalidAscii {
+                plaintext: hex::vec_u8_to_string(e.plaintext.clone()),
+            }
+        } else {
+            WireError::Other {
+                message: e.to_string(),
+            }
+        }
+    }
+
+    pub(crate) fn into_error(self) -> Box<dyn Error + Send + Sync + 'static> {
+        match self {
+            WireError::Transient => Box::new(TransientError),
+            WireError::RateLimited { retry_after } => Box::new(RateLimited { retry_after }),
+            WireError::BudgetExceeded { budget } => Box::new(BudgetExceeded { budget }),
+            WireError::InvalidAscii { plaintext } => match hex::string_to_vec_u8(&plaintext) {
+                Ok(plaintext) => Box::new(InvalidAscii { plaintext }),
+                Err(e) => e,
+            },
+            WireError::Other { message } => message.into(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::{dict_block, find_char_in_dict, length_change, retry_delay, Result, FILLER, RETRIES};
+use futures_util::future;
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use oracle::AsyncEncryptionOracle;
+use std::time::Duration;
+
+/// Same as `retry` around an encryption query, sleeping on the tokio timer.
+async fn encrypt_with_retry<T: AsyncEncryptionOracle + ?Sized>(
+    oracle: &T,
+    data: &[u8],
+) -> Result<Vec<u8>> {
+    let mut backoff = Duration::from_millis(1);
+
+    for _ in 1..RETRIES {
+        match oracle.encrypt(data).await {
+            Err(e) => match retry_delay(&*e, backoff) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => return Err(e),
+            },
+            result => return result,
+        }
+        backoff *= 2;
+    }
+
+    oracle.encrypt(data).await
+}
+
+/// Same as `build_dict`, with up to `concurrency` queries in flight. A concurrency of 0 is taken
+/// as 1, as no query would ever be sent.
+pub async fn build_dict_async<T: AsyncEncryptionOracle + Sync + ?Sized>(
+    known: &[u8],
+    oracle: &T,
+    block_size: usize,
+    concurrency: usize,
+) -> Result<Vec<Vec<u8>>> {
+    let block = dict_block(known, block_size);
+
+    stream::iter(0..=255u8)
+        .map(|i| {
+            let mut block = block.clone();
+            block[block_size - 1] = i;
+            async move { Ok(encrypt_with_retry(oracle, &block).await?[0..block_size].to_vec()) }
+        })
+        .buffered(concurrency.max(1))
+        .try_collect()
+        .await
+}
+
+/// Measures the block size and the suffix length with the same queries as `detect_blocksize`.
+async fn detect_blocksize_and_suffix_len<T: AsyncEncryptionOracle + ?Sized>(
+    oracle: &T,
+) -> Result<(usize, usize)> {
+    let mut payload = Vec::new();
+    let zero_len = encrypt_with_retry(oracle, &payload).await?.len();
+
+    loop {
+        payload.push(0u8);
+        let len = encrypt_with_retry(oracle, &payload).await?.len();
+
+        if let Some(found) = length_change(zero_len, payload.len(), len) {
+            return Ok(found);
+        }
+    }
+}
+
+/// Same as `recover_ecb_suffix`, with up to `concurrency` queries in flight while guessing each
+/// byte.
+pub async fn recover_ecb_suffix_async<T: AsyncEncryptionOracle + Sync + ?Sized>(
+    oracle: &T,
+    concurrency: usize,
+) -> Result<Vec<u8>> {
+    let (block_size, suffix_len) = detect_blocksize_and_suffix_len(oracle).await?;
+    let mut plaintext: Vec<u8> = Vec::new();
+
+    while plaintext.len() < suffix_len {
+        let payload = vec![FILLER; block_size - plaintext.len() % block_size - 1];
+        // The lookup query does not depend on the dictionary, send it alongside
+        let (dict, cipher) = future::try_join(
+            build_dict_async(&plaintext, oracle, block_size, concurrency),
+            encrypt_with_retry(oracle, &payload),
+        )
+        .await?;
+
+        let start = plaintext.len() / block_size * block_size;
+        let found_char = find_char_in_dict(&dict, &cipher[start..start + block_size])?;
+        plaintext.push(found_char);
+    }
+
+    Ok(plaintext)
+}
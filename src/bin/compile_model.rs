@@ -0,0 +1,33 @@
+use rust_cryptopals::LanguageModel;
+use std::env;
+use std::fs;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+/// Compiles the byte n-grams of one or more corpora into a model file.
+///
+/// Usage: compile_model <order> <output> <corpus>...
+fn main() -> Result<()> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.len() < 3 {
+        return Err("Usage: compile_model <order> <output> <corpus>...".into());
+    }
+
+    let mut corpus = Vec::new();
+    for path in &args[2..] {
+        corpus.extend(fs::read(path)?);
+    }
+
+    let model = LanguageModel::train(&corpus, args[0].parse()?)?;
+    model.save(&args[1])?;
+
+    println!(
+        "{}: {} distinct {}-grams out of {}",
+        args[1],
+        model.counts().len(),
+        model.order(),
+        model.total()
+    );
+
+    Ok(())
+}
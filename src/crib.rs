@@ -0,0 +1,165 @@
+use crate::{Result, Scorer};
+use std::io::{BufRead, Write};
+
+/// Position of a crib in one of the ciphertexts, scored on how the keystream it implies decrypts
+/// the other ciphertexts.
+#[derive(Debug, PartialEq)]
+pub struct CribMatch {
+    pub cipher: usize,
+    pub offset: usize,
+    pub score: f32,
+}
+
+/// Works out by hand the keystream of ciphertexts encrypted under one CTR key and nonce, usually
+/// starting from `crack_fixed_nonce`: plaintext guessed at some position of a ciphertext fixes
+/// the keystream there, for all the ciphertexts.
+pub struct CribDragger {
+    ciphers: Vec<Vec<u8>>,
+    keystream: Vec<u8>,
+}
+
+impl CribDragger {
+    /// `keystream` is extended with zeros up to the length of the longest ciphertext.
+    pub fn new(ciphers: Vec<Vec<u8>>, mut keystream: Vec<u8>) -> Self {
+        let len = ciphers.iter().map(Vec::len).max().unwrap_or(0);
+        if keystream.len() < len {
+            keystream.resize(len, 0);
+        }
+
+        CribDragger { ciphers, keystream }
+    }
+
+    pub fn keystream(&self) -> &[u8] {
+        &self.keystream
+    }
+
+    pub fn plaintexts(&self) -> Vec<Vec<u8>> {
+        self.ciphers
+            .iter()
+            .map(|c| c.iter().zip(&self.keystream).map(|(c, k)| c ^ k).collect())
+            .collect()
+    }
+
+    /// Declares that ciphertext `cipher` reads `crib` from `offset`.
+    pub fn fix(&mut self, cipher: usize, offset: usize, crib: &[u8]) -> Result<()> {
+        let bytes = match self.ciphers.get(cipher) {
+            Some(c) if offset + crib.len() <= c.len() => &c[offset..offset + crib.len()],
+            _ => {
+                return Err(format!(
+                    "Ciphertext {} has no room for {} bytes at offset {}",
+                    cipher,
+                    crib.len(),
+                    offset
+                )
+                .into())
+            }
+        };
+
+        for (i, (c, p)) in bytes.iter().zip(crib).enumerate() {
+            self.keystream[offset + i] = c ^ p;
+        }
+
+        Ok(())
+    }
+
+    /// Tries `crib` at every position of every ciphertext, scoring each with what the other
+    /// ciphertexts decrypt to there, per byte. Best matches first.
+    pub fn drag<S: Scorer + ?Sized>(&self, crib: &[u8], scorer: &S) -> Vec<CribMatch> {
+        let mut matches = Vec::new();
+
+        for (index, cipher) in self.ciphers.iter().enumerate() {
+            for offset in 0..(cipher.len() + 1).saturating_sub(crib.len()) {
+                let keystream: Vec<u8> = cipher[offset..offset + crib.len()]
+                    .iter()
+                    .zip(crib)
+                    .map(|(c, p)| c ^ p)
+                    .collect();
+
+                let (mut total, mut count) = (0.0f32, 0);
+                for (_, other) in self.ciphers.iter().enumerate().filter(|(i, _)| *i != index) {
+                    let decrypted: Vec<u8> = other
+                        .iter()
+                        .skip(offset)
+                        .zip(&keystream)
+                        .map(|(c, k)| c ^ k)
+                        .collect();
+                    total += scorer.score(&decrypted);
+                    count += decrypted.len();
+                }
+
+                if count > 0 {
+                    matches.push(CribMatch {
+                        cipher: index,
+                        offset,
+                        score: total / count as f32,
+                    });
+                }
+            }
+        }
+
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches
+    }
+
+    /// Interactive session: prints the plaintexts, then runs the commands read from `input` until
+    /// `quit` or the end of input. `fix <cipher> <offset> <text>` fixes the keystream and prints
+    /// the plaintexts again, `drag <text>` prints the best positions for a crib.
+    pub fn interact<S: Scorer + ?Sized, R: BufRead, W: Write>(
+        &mut self,
+        scorer: &S,
+        input: R,
+        mut output: W,
+    ) -> Result<()> {
+        self.print_plaintexts(&mut output)?;
+
+        for line in input.lines() {
+            let line = line?;
+            let mut words = line.splitn(2, ' ');
+
+            match (words.next(), words.next()) {
+                (Some("quit"), _) => break,
+                (Some("fix"), Some(args)) => match self.fix_command(args) {
+                    Ok(()) => self.print_plaintexts(&mut output)?,
+                    Err(e) => writeln!(output, "{}", e)?,
+                },
+                (Some("drag"), Some(crib)) => {
+                    for m in self.drag(crib.as_bytes(), scorer).iter().take(10) {
+                        writeln!(
+                            output,
+                            "cipher {:>3} offset {:>3} score {:.4}",
+                            m.cipher, m.offset, m.score
+                        )?;
+                    }
+                }
+                _ => writeln!(
+                    output,
+                    "Commands: fix <cipher> <offset> <text>, drag <text>, quit"
+                )?,
+            }
+        }
+
+        Ok(())
+    }
+
+    fn fix_command(&mut self, args: &str) -> Result<()> {
+        match args.splitn(3, ' ').collect::<Vec<_>>()[..] {
+            [cipher, offset, text] => self.fix(cipher.parse()?, offset.parse()?, text.as_bytes()),
+            _ => Err("Usage: fix <cipher> <offset> <text>".into()),
+        }
+    }
+
+    fn print_plaintexts<W: Write>(&self, output: &mut W) -> Result<()> {
+        for (i, plaintext) in self.plaintexts().iter().enumerate() {
+            let shown: String = plaintext
+                .iter()
+                .map(|&b| match b == b' ' || b.is_ascii_graphic() {
+                    true => b as char,
+                    false => '.',
+                })
+                .collect();
+            writeln!(output, "{:>3} {}", i, shown)?;
+        }
+
+        Ok(())
+    }
+}
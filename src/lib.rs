@@ -2,12 +2,84 @@ extern crate aes_oracle;
 extern crate base64;
 extern crate cookie_oracle;
 
-use oracle::Oracle;
-use std::fs;
+use oracle::{
+    DecryptionOracle, EditOracle, EncryptionOracle, InvalidAscii, PaddingOracle, RateLimited,
+    TransientError,
+};
+use std::collections::HashMap;
+use std::error::Error;
+use std::hash::Hash;
 use std::str;
+use std::thread;
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+mod asynchronous;
+mod crib;
+mod models;
+mod scoring;
+
+#[cfg(feature = "async")]
+pub use asynchronous::{build_dict_async, recover_ecb_suffix_async};
+pub use crib::{CribDragger, CribMatch};
+pub use models::{Language, LanguageModel};
+pub use scoring::{ChiSquared, NGram, PrintableRatio, Scorer, Unigram};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
+/// Attempts at a query failing with a transient error or rate limit before giving up.
+const RETRIES: usize = 10;
+/// Independent runs of a detection routine, the most frequent result wins.
+const VOTES: usize = 3;
+
+/// Time to wait before sending again a query that failed with `e`, or None if retrying would
+/// not help.
+fn retry_delay(e: &(dyn Error + Send + Sync + 'static), backoff: Duration) -> Option<Duration> {
+    if e.is::<TransientError>() {
+        Some(backoff)
+    } else {
+        e.downcast_ref::<RateLimited>()
+            .map(|limited| limited.retry_after.max(backoff))
+    }
+}
+
+/// Runs `query` again while it fails with a transient error, waiting out rate limits.
+fn retry<T>(mut query: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut backoff = Duration::from_millis(1);
+
+    for _ in 1..RETRIES {
+        match query() {
+            Err(e) => match retry_delay(&*e, backoff) {
+                Some(delay) => thread::sleep(delay),
+                None => return Err(e),
+            },
+            result => return result,
+        }
+        backoff *= 2;
+    }
+
+    query()
+}
+
+fn encrypt_with_retry<T: EncryptionOracle + ?Sized>(oracle: &T, data: &[u8]) -> Result<Vec<u8>> {
+    retry(|| oracle.encrypt(data))
+}
+
+/// Runs `detect` `VOTES` times and keeps the most frequent result, so a single disturbed run
+/// does not mislead an attack.
+fn vote<T: Eq + Hash>(mut detect: impl FnMut() -> Result<T>) -> Result<T> {
+    let mut counts = HashMap::new();
+    for _ in 0..VOTES {
+        *counts.entry(detect()?).or_insert(0) += 1;
+    }
+
+    Ok(counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(result, _)| result)
+        .expect("at least one vote"))
+}
+
 pub fn do_xor(left: Vec<u8>, right: Vec<u8>) -> Result<Vec<u8>> {
     if right.len() != right.len() {
         panic!("Not same length {} - {}", left.len(), right.len());
@@ -22,43 +94,26 @@ pub fn do_xor(left: Vec<u8>, right: Vec<u8>) -> Result<Vec<u8>> {
     Ok(out_bytes)
 }
 
-pub fn do_single_xor(input: &Vec<u8>, k: u8) -> Result<Vec<u8>> {
+pub fn do_single_xor(input: &[u8], k: u8) -> Result<Vec<u8>> {
     Ok(input.iter().map(|b| *b ^ k).collect())
 }
 
-pub fn crack_single_xor(input: &Vec<u8>, dict: &[f32; 256]) -> Result<(u8, f32)> {
-    let mut max_score: f32 = 0.0f32;
-    let mut key = 0u8;
+/// Scores the decryption of `input` under every single byte key, best candidates first.
+pub fn crack_single_xor<S: Scorer + ?Sized>(input: &[u8], scorer: &S) -> Result<Vec<(u8, f32)>> {
+    let mut candidates = (0..=255)
+        .map(|k| Ok((k, scorer.score(&do_single_xor(input, k)?))))
+        .collect::<Result<Vec<(u8, f32)>>>()?;
 
-    for k in 0..=255 {
-        let xored = do_single_xor(input, k)?;
-        let score = xored.iter().map(|b| dict[*b as usize]).sum();
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
 
-        if score > max_score {
-            max_score = score;
-            key = k;
-        }
-    }
-
-    Ok((key, max_score))
+    Ok(candidates)
 }
 
 pub fn build_charstat_dict(path: &str) -> Result<[f32; 256]> {
-    let file = fs::read_to_string(path)?;
-    let mut out_dict = [0.0f32; 256];
-
-    for c in file.bytes() {
-        out_dict[c as usize] += 1.0f32;
-    }
-
-    for e in &mut out_dict {
-        *e /= file.len() as f32;
-    }
-
-    Ok(out_dict)
+    Ok(*Unigram::from_file(path)?.freqs())
 }
 
-pub fn do_vigenere(input: &Vec<u8>, key: &Vec<u8>) -> Result<Vec<u8>> {
+pub fn do_vigenere(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     let mut out: Vec<u8> = Vec::with_capacity(input.len());
 
     for i in 0..input.len() {
@@ -68,32 +123,83 @@ pub fn do_vigenere(input: &Vec<u8>, key: &Vec<u8>) -> Result<Vec<u8>> {
     Ok(out)
 }
 
-pub fn crack_vigenere(input: &[u8]) -> Result<Vec<u8>> {
-    let dict = build_charstat_dict("./data/pride_and_prejudice.txt")?;
-    let key_size = guess_key_size(&input)?;
+/// Key sizes whose best key `crack_vigenere` tries.
+const KEY_SIZE_CANDIDATES: usize = 3;
 
-    println!("Probable key size: {}", key_size);
+/// Cracks the repeating key XOR of `input` for the most probable key sizes, each key byte being
+/// the best of `crack_single_xor` on its column. Keys come ranked by the score of the whole
+/// plaintext they decrypt, best first.
+pub fn crack_vigenere<S: Scorer + ?Sized>(input: &[u8], scorer: &S) -> Result<Vec<(Vec<u8>, f32)>> {
+    let mut candidates = Vec::new();
 
-    let mut vectors: Vec<Vec<u8>> = (0..key_size).map(|_| Vec::new()).collect();
+    for (key_size, _) in guess_key_sizes(input)?
+        .into_iter()
+        .take(KEY_SIZE_CANDIDATES)
+    {
+        let mut vectors: Vec<Vec<u8>> = (0..key_size).map(|_| Vec::new()).collect();
 
-    for chunk in input.chunks(key_size) {
-        for (i, byte) in chunk.iter().enumerate() {
-            vectors[i].push(*byte);
+        for chunk in input.chunks(key_size) {
+            for (i, byte) in chunk.iter().enumerate() {
+                vectors[i].push(*byte);
+            }
         }
-    }
 
-    let mut key = Vec::with_capacity(key_size);
-    for vector in vectors {
-        let (k, _) = crack_single_xor(&vector, &dict)?;
-        key.push(k);
+        let mut key = Vec::with_capacity(key_size);
+        for vector in vectors {
+            key.push(crack_single_xor(&vector, scorer)?[0].0);
+        }
+
+        let score = scorer.score(&do_vigenere(input, &key)?);
+        candidates.push((key, score));
     }
 
-    Ok(key)
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    Ok(candidates)
+}
+
+/// Recovers the keystream shared by CTR ciphertexts encrypted under the same key and nonce: each
+/// column of bytes is XORed with a single keystream byte, cracked with `crack_single_xor`.
+///
+/// With `truncate` the keystream stops at the shortest ciphertext, otherwise it covers the
+/// longest one, the last columns being cracked from fewer and fewer ciphertexts. Each byte comes
+/// with its confidence: the share of the ciphertexts it was cracked from.
+pub fn crack_fixed_nonce<S: Scorer + ?Sized>(
+    ciphers: &[Vec<u8>],
+    scorer: &S,
+    truncate: bool,
+) -> Result<Vec<(u8, f32)>> {
+    let lengths = ciphers.iter().map(Vec::len);
+    let len = match truncate {
+        true => lengths.min(),
+        false => lengths.max(),
+    }
+    .unwrap_or(0);
+
+    (0..len)
+        .map(|i| {
+            let column: Vec<u8> = ciphers.iter().filter_map(|c| c.get(i).copied()).collect();
+            let key = crack_single_xor(&column, scorer)?[0].0;
+            Ok((key, column.len() as f32 / ciphers.len() as f32))
+        })
+        .collect()
 }
 
 pub fn guess_key_size(input: &[u8]) -> Result<usize> {
-    let mut min_distance = f32::MAX;
-    let mut best_key_size: usize = 0;
+    let (best_key_size, min_distance) = guess_key_sizes(input)?[0];
+
+    println!(
+        "Guessed key size {} with min distance {}",
+        best_key_size, min_distance
+    );
+
+    Ok(best_key_size)
+}
+
+/// Key sizes from 2 to 39 with their normalized Hamming distance between the first blocks of
+/// `input`, most probable first.
+pub fn guess_key_sizes(input: &[u8]) -> Result<Vec<(usize, f32)>> {
+    let mut key_sizes = Vec::new();
 
     for key_size in 2..40 {
         let chunks: Vec<&[u8]> = input.chunks(key_size).take(4).collect();
@@ -106,20 +212,12 @@ pub fn guess_key_size(input: &[u8]) -> Result<usize> {
             }
         }
 
-        distance /= key_size as f32;
-
-        if distance < min_distance {
-            min_distance = distance;
-            best_key_size = key_size;
-        }
+        key_sizes.push((key_size, distance / key_size as f32));
     }
 
-    println!(
-        "Guessed key size {} with min distance {}",
-        best_key_size, min_distance
-    );
+    key_sizes.sort_by(|a, b| a.1.total_cmp(&b.1));
 
-    Ok(best_key_size)
+    Ok(key_sizes)
 }
 
 pub fn hamming_distance(left: &[u8], right: &[u8]) -> Result<u32> {
@@ -140,7 +238,7 @@ pub fn decrypt_aes_128_ecb(input: &[u8], key: &[u8]) -> Result<Vec<u8>> {
     Ok(out)
 }
 
-pub fn detect_ecb(input: &Vec<u8>) -> bool {
+pub fn detect_ecb(input: &[u8]) -> bool {
     let mut chunks: Vec<_> = input.chunks(16).collect();
     let len = chunks.len();
     chunks.sort();
@@ -149,17 +247,17 @@ pub fn detect_ecb(input: &Vec<u8>) -> bool {
     chunks.len() != len
 }
 
-pub fn find_char_in_dict(dict: &Vec<Vec<u8>>, block: &[u8]) -> Result<u8> {
+pub fn find_char_in_dict(dict: &[Vec<u8>], block: &[u8]) -> Result<u8> {
     // Run through the guessing dict and find which byte it was
-    for i in 0..256 {
+    for (i, entry) in dict.iter().enumerate() {
         let mut good = true;
         for (j, byte) in block.iter().enumerate() {
-            if *byte != dict[i][j] {
+            if *byte != entry[j] {
                 good = false;
                 break;
             }
         }
-        if good == true {
+        if good {
             return Ok(i as u8);
         }
     }
@@ -167,30 +265,52 @@ pub fn find_char_in_dict(dict: &Vec<Vec<u8>>, block: &[u8]) -> Result<u8> {
     panic!("Could not find next byte.")
 }
 
-pub fn build_dict<T: Oracle>(
-    known: &Vec<u8>,
+/// Byte filling the attacker controlled blocks of the byte-at-a-time ECB attacks.
+const FILLER: u8 = b'A';
+
+/// Last `block_size - 1` known bytes, left filled with `FILLER`, followed by the byte to guess.
+fn dict_block(known: &[u8], block_size: usize) -> Vec<u8> {
+    let mut block = vec![FILLER; block_size];
+
+    block.extend_from_slice(known);
+    block.push(FILLER);
+    block.iter().cloned().rev().take(block_size).rev().collect()
+}
+
+pub fn build_dict<T: EncryptionOracle + ?Sized>(
+    known: &[u8],
     oracle: &T,
     block_size: usize,
 ) -> Result<Vec<Vec<u8>>> {
-    let mut out = vec![Vec::new(); 256];
-    let mut block = vec![65u8; block_size];
+    build_dict_aligned(known, oracle, block_size, &[], 0)
+}
 
-    block.extend_from_slice(known);
-    block.push(65u8);
-    block = block.iter().cloned().rev().take(block_size).rev().collect();
+/// `build_dict` against an oracle prepending a prefix: `align` is sent first to complete the
+/// last block of the prefix, and the guessed block is read `skip` bytes into the ciphertext.
+fn build_dict_aligned<T: EncryptionOracle + ?Sized>(
+    known: &[u8],
+    oracle: &T,
+    block_size: usize,
+    align: &[u8],
+    skip: usize,
+) -> Result<Vec<Vec<u8>>> {
+    let mut out = vec![Vec::new(); 256];
+    let mut query = align.to_vec();
+    query.extend(dict_block(known, block_size));
+    let guessed = query.len() - 1;
 
-    for i in 0..256 {
-        block[block_size - 1] = i as u8;
-        out[i] = oracle.encrypt(&block)?[0..block_size].to_vec();
+    for (i, entry) in out.iter_mut().enumerate() {
+        query[guessed] = i as u8;
+        *entry = encrypt_with_retry(oracle, &query)?[skip..skip + block_size].to_vec();
     }
 
     Ok(out)
 }
 
-pub fn detect_encryption_mode(oracle: &impl Oracle) -> Result<aes::MODE> {
+pub fn detect_encryption_mode<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<aes::MODE> {
     let input = [0u8; 48];
 
-    let cipher = oracle.encrypt(&input)?;
+    let cipher = encrypt_with_retry(oracle, &input)?;
 
     match detect_ecb(&cipher) {
         true => Ok(aes::MODE::ECB),
@@ -198,11 +318,27 @@ pub fn detect_encryption_mode(oracle: &impl Oracle) -> Result<aes::MODE> {
     }
 }
 
-pub fn detect_blocksize(oracle: &impl Oracle) -> Result<usize> {
+pub fn detect_blocksize<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<usize> {
+    vote(|| Ok(detect_blocksize_and_suffix_len(oracle)?.0))
+}
+
+/// Block size and length of the data added to the input, once adding `added` bytes of input grew
+/// the cipher from `zero_len` to `len` bytes. None while the cipher did not grow.
+fn length_change(zero_len: usize, added: usize, len: usize) -> Option<(usize, usize)> {
+    if len != zero_len {
+        Some((len - zero_len, zero_len - added))
+    } else {
+        None
+    }
+}
+
+fn detect_blocksize_and_suffix_len<T: EncryptionOracle + ?Sized>(
+    oracle: &T,
+) -> Result<(usize, usize)> {
     let mut payload = Vec::new();
 
     // Get the size of the initial cipher
-    let zero_len = oracle.encrypt(&payload)?.len();
+    let zero_len = encrypt_with_retry(oracle, &payload)?.len();
     /*
     xxxx xxxx x___      -> 12
     --------------
@@ -218,39 +354,39 @@ pub fn detect_blocksize(oracle: &impl Oracle) -> Result<usize> {
         // is different than the initial cipher's length, it means that we reached
         // the padding size and hence that we can guess the block size.
         payload.push(0u8);
-        let len = oracle.encrypt(&payload)?.len();
+        let len = encrypt_with_retry(oracle, &payload)?.len();
 
-        if len != zero_len {
-            return Ok(len - zero_len);
+        if let Some(found) = length_change(zero_len, payload.len(), len) {
+            return Ok(found);
         }
     }
 }
 
-pub fn detect_prefix_blocks_count<T: Oracle>(oracle: &T) -> Result<usize> {
+pub fn detect_prefix_blocks_count<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<usize> {
     let block_size = detect_blocksize(oracle)?;
-    if let Some(result) = oracle
-        .encrypt(&[0])?
-        .chunks(block_size)
-        .zip(oracle.encrypt(&[1])?.chunks(block_size))
-        .position(|(x, y)| x != y)
-    {
-        Ok(result)
-    } else {
-        panic!("Unable to find number of blocks occupied by oracle prefix")
-    }
+
+    vote(|| {
+        if let Some(result) = encrypt_with_retry(oracle, &[0])?
+            .chunks(block_size)
+            .zip(encrypt_with_retry(oracle, &[1])?.chunks(block_size))
+            .position(|(x, y)| x != y)
+        {
+            Ok(result)
+        } else {
+            panic!("Unable to find number of blocks occupied by oracle prefix")
+        }
+    })
 }
 
-pub fn detect_prefix_len<T: Oracle>(oracle: &T) -> Result<usize> {
+pub fn detect_prefix_len<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<usize> {
     let block_size = detect_blocksize(oracle)?;
     let offset = detect_prefix_blocks_count(oracle)? * block_size;
 
-    println!("offset: {}", offset);
-
     let do_with_constant = |c: u8| -> Result<usize> {
         let cblock = vec![c; block_size];
-        let initial_block = &oracle.encrypt(&cblock)?[offset..(offset + block_size)];
+        let initial_block = &encrypt_with_retry(oracle, &cblock)?[offset..(offset + block_size)];
         for i in 0..block_size {
-            let current = oracle.encrypt(&cblock[i + 1..])?;
+            let current = encrypt_with_retry(oracle, &cblock[i + 1..])?;
             if current.len() < offset + block_size
                 || initial_block != &current[offset..(offset + block_size)]
             {
@@ -263,21 +399,12 @@ pub fn detect_prefix_len<T: Oracle>(oracle: &T) -> Result<usize> {
     Ok(offset + std::cmp::min(do_with_constant(0)?, do_with_constant(1)?))
 }
 
-pub fn detect_suffix_len<T: Oracle>(oracle: &T) -> Result<usize> {
-    let initial_size = oracle.encrypt(&[])?.len();
-    let mut current_size = initial_size;
-    let mut payload = Vec::new();
-
-    while current_size == initial_size {
-        payload.push(0u8);
-        current_size = oracle.encrypt(&payload)?.len();
-    }
-
-    Ok(initial_size - payload.len())
+pub fn detect_suffix_len<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<usize> {
+    Ok(detect_blocksize_and_suffix_len(oracle)?.1)
 }
 
-pub fn detect_prefix_plus_suffix_len<T: Oracle>(oracle: &T) -> Result<usize> {
-    let initial_size = oracle.encrypt(&[])?.len();
+pub fn detect_prefix_plus_suffix_len<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<usize> {
+    let initial_size = encrypt_with_retry(oracle, &[])?.len();
     if !detect_padding(oracle)? {
         return Ok(initial_size);
     }
@@ -285,43 +412,380 @@ pub fn detect_prefix_plus_suffix_len<T: Oracle>(oracle: &T) -> Result<usize> {
     let bs = detect_blocksize(oracle)?;
 
     let input = vec![0; bs];
-    if let Some(index) = (1..=bs).find(|&i| {
-        if let Ok(ciphertext) = oracle.encrypt(&input[..i]) {
-            initial_size != ciphertext.len()
-        } else {
-            // Should never happen
-            false
+    for i in 1..=bs {
+        if encrypt_with_retry(oracle, &input[..i])?.len() != initial_size {
+            return Ok(initial_size - i);
         }
-    }) {
-        Ok(initial_size - index + 1)
-    } else {
-        Err(
-            "length of oracle output did not change, something is wrong with the provided oracle"
-                .into(),
-        )
     }
+
+    Err(
+        "length of oracle output did not change, something is wrong with the provided oracle"
+            .into(),
+    )
 }
 
-pub fn detect_padding<T: Oracle>(oracle: &T) -> Result<bool> {
-    Ok((oracle.encrypt(&[])?.len() - oracle.encrypt(&[0])?.len()) % detect_blocksize(oracle)? == 0)
+pub fn detect_padding<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<bool> {
+    let block_size = detect_blocksize(oracle)?;
+
+    vote(|| {
+        let empty_len = encrypt_with_retry(oracle, &[])?.len();
+        let one_len = encrypt_with_retry(oracle, &[0])?.len();
+
+        Ok(empty_len.abs_diff(one_len).is_multiple_of(block_size))
+    })
 }
 
-pub fn recover_ecb_suffix<T: Oracle>(oracle: &T) -> Result<Vec<u8>> {
+pub fn recover_ecb_suffix<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<Vec<u8>> {
     let block_size = detect_blocksize(oracle)?;
+    let suffix_len = detect_suffix_len(oracle)?;
+
+    recover_ecb_suffix_aligned(oracle, block_size, 0, suffix_len)
+}
+
+/// Same as `recover_ecb_suffix` against an oracle that also prepends a fixed unknown prefix.
+pub fn recover_ecb_suffix_with_prefix<T: EncryptionOracle + ?Sized>(oracle: &T) -> Result<Vec<u8>> {
+    let block_size = detect_blocksize(oracle)?;
+    let prefix_len = detect_prefix_len(oracle)?;
+    let suffix_len = detect_prefix_plus_suffix_len(oracle)? - prefix_len;
+
+    recover_ecb_suffix_aligned(oracle, block_size, prefix_len, suffix_len)
+}
+
+fn recover_ecb_suffix_aligned<T: EncryptionOracle + ?Sized>(
+    oracle: &T,
+    block_size: usize,
+    prefix_len: usize,
+    suffix_len: usize,
+) -> Result<Vec<u8>> {
+    // Complete the last block of the prefix so our data starts on a block boundary
+    let align = vec![FILLER; (block_size - prefix_len % block_size) % block_size];
+    let skip = prefix_len + align.len();
     let mut plaintext: Vec<u8> = Vec::new();
 
-    while plaintext.len() < detect_suffix_len(oracle)? {
+    while plaintext.len() < suffix_len {
         // Build the guessing dictionary
-        let dict = build_dict(&plaintext, oracle, block_size)?;
+        let dict = build_dict_aligned(&plaintext, oracle, block_size, &align, skip)?;
         // Build the payload
-        let payload = vec![65u8; block_size - plaintext.len() % block_size - 1];
-        // Get the first block out of the cipher
-        let start = plaintext.len() / block_size * block_size;
-        let block = &oracle.encrypt(&payload)?[start..start + block_size];
+        let mut payload = align.clone();
+        payload.resize(
+            align.len() + block_size - plaintext.len() % block_size - 1,
+            FILLER,
+        );
+        // Get the block holding the next byte out of the cipher
+        let start = skip + plaintext.len() / block_size * block_size;
+        let block = &encrypt_with_retry(oracle, &payload)?[start..start + block_size];
         // Add found byte to plaintext
-        let found_char = find_char_in_dict(&dict, &block)?;
+        let found_char = find_char_in_dict(&dict, block)?;
         plaintext.push(found_char);
     }
 
     Ok(plaintext)
 }
+
+/// Bytes of the first and second sentinel blocks marking the start of the data in
+/// `encrypt_aligned`.
+const SENTINELS: [u8; 2] = [0x00, 0xff];
+/// Queries `encrypt_aligned` makes before giving up on aligning the sentinel blocks.
+const ALIGN_ATTEMPTS: usize = 1000;
+
+/// Encrypts `data` through an ECB oracle prepending a prefix of random length on every call, and
+/// returns the ciphertext from the block where `data` starts, as if there was no prefix.
+///
+/// `data` is sent behind a varying number of `FILLER` bytes, a block of the first sentinel byte
+/// and a block of the second one: the query is sent again until both sentinel blocks show up
+/// whole in the ciphertext, which only happens when they fall on block boundaries. As the two
+/// blocks differ, bytes around them equal to a sentinel cannot make them match one byte off.
+/// `data` itself should not hold the two sentinel blocks in a row.
+///
+/// `sentinel_blocks` are the ciphertexts of the two sentinel blocks, as returned by
+/// `sentinel_cipher_blocks`.
+pub fn encrypt_aligned<T: EncryptionOracle + ?Sized>(
+    oracle: &T,
+    data: &[u8],
+    sentinel_blocks: &[Vec<u8>; 2],
+    block_size: usize,
+) -> Result<Vec<u8>> {
+    for attempt in 0..ALIGN_ATTEMPTS {
+        let mut payload = vec![FILLER; 1 + attempt % block_size];
+        for sentinel in SENTINELS {
+            payload.resize(payload.len() + block_size, sentinel);
+        }
+        payload.extend_from_slice(data);
+
+        let cipher = encrypt_with_retry(oracle, &payload)?;
+        let blocks: Vec<&[u8]> = cipher.chunks(block_size).collect();
+        if let Some(index) = blocks
+            .windows(2)
+            .position(|pair| pair[0] == sentinel_blocks[0] && pair[1] == sentinel_blocks[1])
+        {
+            return Ok(cipher[(index + 2) * block_size..].to_vec());
+        }
+    }
+
+    Err(format!(
+        "Could not align the sentinel blocks in {} queries",
+        ALIGN_ATTEMPTS
+    )
+    .into())
+}
+
+/// Ciphertexts of a block of each of the sentinel bytes, found once for all the `encrypt_aligned`
+/// calls against `oracle`.
+pub fn sentinel_cipher_blocks<T: EncryptionOracle + ?Sized>(
+    oracle: &T,
+    block_size: usize,
+) -> Result<[Vec<u8>; 2]> {
+    Ok([
+        sentinel_cipher_block(oracle, SENTINELS[0], block_size)?,
+        sentinel_cipher_block(oracle, SENTINELS[1], block_size)?,
+    ])
+}
+
+/// Ciphertext of a block of `sentinel` bytes: whatever the prefix, four blocks of them hold at
+/// least three whole ones.
+fn sentinel_cipher_block<T: EncryptionOracle + ?Sized>(
+    oracle: &T,
+    sentinel: u8,
+    block_size: usize,
+) -> Result<Vec<u8>> {
+    let run = encrypt_with_retry(oracle, &vec![sentinel; 4 * block_size])?;
+    let blocks: Vec<&[u8]> = run.chunks(block_size).collect();
+
+    match blocks.windows(2).find(|pair| pair[0] == pair[1]) {
+        Some(pair) => Ok(pair[0].to_vec()),
+        None => Err("Oracle does not encrypt in ECB mode".into()),
+    }
+}
+
+/// Same as `recover_ecb_suffix` against an oracle prepending a prefix of random length on every
+/// call. Queries go through `encrypt_aligned`, which hides the prefix, and as each of them takes
+/// several attempts the guessing dictionary and the lookup of a byte are sent together.
+pub fn recover_ecb_suffix_random_prefix<T: EncryptionOracle + ?Sized>(
+    oracle: &T,
+    block_size: usize,
+) -> Result<Vec<u8>> {
+    let sentinel_blocks = sentinel_cipher_blocks(oracle, block_size)?;
+    let aligned = |data: &[u8]| encrypt_aligned(oracle, data, &sentinel_blocks, block_size);
+    let suffix_len = detect_suffix_len(&aligned)?;
+    let lookup = 256 * block_size;
+    let mut plaintext: Vec<u8> = Vec::new();
+
+    while plaintext.len() < suffix_len {
+        // The 256 guesses, followed by the payload pushing the next byte at the end of a block
+        let mut block = dict_block(&plaintext, block_size);
+        let mut query = Vec::with_capacity(lookup + block_size);
+        for guess in 0..=255 {
+            block[block_size - 1] = guess;
+            query.extend_from_slice(&block);
+        }
+        query.resize(
+            lookup + block_size - plaintext.len() % block_size - 1,
+            FILLER,
+        );
+
+        let cipher = aligned(&query)?;
+        let dict: Vec<Vec<u8>> = cipher[..lookup]
+            .chunks(block_size)
+            .map(<[u8]>::to_vec)
+            .collect();
+        let start = lookup + plaintext.len() / block_size * block_size;
+        plaintext.push(find_char_in_dict(
+            &dict,
+            &cipher[start..start + block_size],
+        )?);
+    }
+
+    Ok(plaintext)
+}
+
+/// Recovers the plaintext of `cipher`, whose first block is the IV, from an oracle telling only
+/// whether a ciphertext decrypts to valid PKCS#7 padding. The padding is left in place.
+pub fn padding_oracle_decrypt<T: PaddingOracle + ?Sized>(
+    oracle: &T,
+    cipher: &[u8],
+    block_size: usize,
+) -> Result<Vec<u8>> {
+    if !cipher.len().is_multiple_of(block_size) || cipher.len() < 2 * block_size {
+        return Err(format!(
+            "Cipher should be an IV and at least one block of {} bytes, is {} bytes",
+            block_size,
+            cipher.len()
+        )
+        .into());
+    }
+
+    let blocks: Vec<&[u8]> = cipher.chunks(block_size).collect();
+    let mut plaintext = Vec::with_capacity(cipher.len() - block_size);
+
+    for pair in blocks.windows(2) {
+        let decrypted = padding_oracle_decrypt_block(oracle, pair[1])?;
+        plaintext.extend(decrypted.iter().zip(pair[0]).map(|(d, p)| d ^ p));
+    }
+
+    Ok(plaintext)
+}
+
+/// Forges an IV followed by ciphertext that decrypts to `message` with valid PKCS#7 padding,
+/// from an oracle telling only whether a ciphertext decrypts to valid padding. Going from the
+/// last block, which is arbitrary, each block is chosen so the next one decrypts as wanted.
+pub fn padding_oracle_encrypt<T: PaddingOracle + ?Sized>(
+    oracle: &T,
+    message: &[u8],
+    block_size: usize,
+) -> Result<Vec<u8>> {
+    let mut padded = message.to_vec();
+    aes::padding_pkcs7(&mut padded, block_size)?;
+
+    let mut blocks = vec![vec![0u8; block_size]];
+    for wanted in padded.chunks(block_size).rev() {
+        let decrypted = padding_oracle_decrypt_block(oracle, &blocks[0])?;
+        blocks.insert(
+            0,
+            decrypted.iter().zip(wanted).map(|(d, w)| d ^ w).collect(),
+        );
+    }
+
+    Ok(blocks.concat())
+}
+
+/// Finds the block cipher decryption of `block` byte by byte, from the last one, by forging the
+/// block before it so the plaintext ends with valid padding.
+fn padding_oracle_decrypt_block<T: PaddingOracle + ?Sized>(
+    oracle: &T,
+    block: &[u8],
+) -> Result<Vec<u8>> {
+    let block_size = block.len();
+    let mut decrypted = vec![0u8; block_size];
+    let mut query = vec![0u8; 2 * block_size];
+    query[block_size..].copy_from_slice(block);
+
+    for pad in 1..=block_size {
+        let pos = block_size - pad;
+        // Make the already found bytes decrypt to the padding value
+        for i in pos + 1..block_size {
+            query[i] = decrypted[i] ^ pad as u8;
+        }
+
+        let mut found = None;
+        for guess in 0..=255u8 {
+            query[pos] = guess;
+            if !retry(|| oracle.check_cipher_padding(&query))? {
+                continue;
+            }
+
+            // The last byte may have produced a longer valid padding, e.g. "\x02\x02". Changing
+            // the byte before it only keeps the padding valid when it really is "\x01".
+            if pad == 1 && block_size > 1 {
+                query[pos - 1] ^= 0xff;
+                let single = retry(|| oracle.check_cipher_padding(&query))?;
+                query[pos - 1] ^= 0xff;
+                if !single {
+                    continue;
+                }
+            }
+
+            found = Some(guess);
+            break;
+        }
+
+        match found {
+            Some(guess) => decrypted[pos] = guess ^ pad as u8,
+            None => return Err(format!("No value of byte {} gives valid padding", pos).into()),
+        }
+    }
+
+    Ok(decrypted)
+}
+
+/// Tampers with a CBC `cipher` so that the plaintext of its block `block` reads `target` where it
+/// read `known`, by XORing their difference into the block before it, which then decrypts to
+/// garbage. Blocks are counted in `cipher`, a prepended IV being block 0.
+pub fn cbc_bitflip(
+    cipher: &[u8],
+    block_size: usize,
+    block: usize,
+    known: &[u8],
+    target: &[u8],
+) -> Result<Vec<u8>> {
+    if known.len() != target.len() || target.len() > block_size {
+        return Err(format!(
+            "Known and target plaintexts should have the same length, at most {}, are {} and {}",
+            block_size,
+            known.len(),
+            target.len()
+        )
+        .into());
+    }
+    if block == 0 || (block + 1) * block_size > cipher.len() {
+        return Err(format!("Cannot flip bits into block {} of the cipher", block).into());
+    }
+
+    let mut tampered = cipher.to_vec();
+    let previous = (block - 1) * block_size;
+    for (i, (k, t)) in known.iter().zip(target).enumerate() {
+        tampered[previous + i] ^= k ^ t;
+    }
+
+    Ok(tampered)
+}
+
+/// Tampers with a stream cipher `cipher` so that its plaintext reads `target` from `offset` where
+/// it read `known`. Unlike CBC, no other byte is affected.
+pub fn ctr_bitflip(cipher: &[u8], offset: usize, known: &[u8], target: &[u8]) -> Result<Vec<u8>> {
+    if known.len() != target.len() || offset + target.len() > cipher.len() {
+        return Err(format!(
+            "Cannot flip {} known bytes into {} target bytes at offset {} of a {} bytes cipher",
+            known.len(),
+            target.len(),
+            offset,
+            cipher.len()
+        )
+        .into());
+    }
+
+    let mut tampered = cipher.to_vec();
+    for (i, (k, t)) in known.iter().zip(target).enumerate() {
+        tampered[offset + i] ^= k ^ t;
+    }
+
+    Ok(tampered)
+}
+
+/// Recovers the plaintext of a stream cipher `cipher` from an oracle editing it in place:
+/// rewriting the whole plaintext with zeros gives back the keystream.
+pub fn recover_plaintext_with_edit<T: EditOracle + ?Sized>(
+    oracle: &T,
+    cipher: &[u8],
+) -> Result<Vec<u8>> {
+    let keystream = retry(|| oracle.edit(cipher, 0, &vec![0u8; cipher.len()]))?;
+
+    do_xor(cipher.to_vec(), keystream)
+}
+
+/// Recovers the key of a CBC oracle using it as IV, from decryptions leaking their plaintext
+/// through `InvalidAscii` errors. The first ciphertext block C1 is sent as C1‖0‖C1: the first
+/// plaintext block is its decryption XORed with the key, the third one its decryption alone.
+pub fn recover_key_as_iv<T: EncryptionOracle + DecryptionOracle + ?Sized>(
+    oracle: &T,
+    block_size: usize,
+) -> Result<Vec<u8>> {
+    let cipher = encrypt_with_retry(oracle, &vec![FILLER; 3 * block_size])?;
+    let first = &cipher[..block_size];
+
+    let mut forged = first.to_vec();
+    forged.extend(vec![0u8; block_size]);
+    forged.extend_from_slice(first);
+
+    let plaintext = match retry(|| oracle.decrypt(&forged)) {
+        Ok(plaintext) => plaintext,
+        Err(e) => match e.downcast::<InvalidAscii>() {
+            Ok(invalid) => invalid.plaintext,
+            Err(e) => return Err(e),
+        },
+    };
+
+    do_xor(
+        plaintext[..block_size].to_vec(),
+        plaintext[2 * block_size..3 * block_size].to_vec(),
+    )
+}
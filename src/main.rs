@@ -1,16 +1,31 @@
 mod set1;
 mod set2;
+mod set3;
+mod set4;
 
 use crate::set1::*;
 use crate::set2::*;
+use crate::set3::*;
+use crate::set4::*;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
 fn main() -> Result<()> {
-    set1()?;
-    set2()?;
+    // Oracles draw their keys from the run seed, print it so failing runs can be replayed
+    if let Err(e) = oracle::report_seed_on_panic() {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    }
 
-    Ok(())
+    let result = set1()
+        .and_then(|_| set2())
+        .and_then(|_| set3())
+        .and_then(|_| set4());
+    if let (Err(_), Ok(seed)) = (&result, oracle::run_seed()) {
+        eprintln!("Replay this run with {}={}", oracle::SEED_ENV, seed);
+    }
+
+    result
 }
 
 fn set1() -> Result<()> {
@@ -32,6 +47,22 @@ fn set2() -> Result<()> {
     challenge11()?;
     challenge12()?;
     challenge13()?;
+    challenge14()?;
+    challenge16()?;
+
+    Ok(())
+}
+
+fn set3() -> Result<()> {
+    challenge17()?;
+
+    Ok(())
+}
+
+fn set4() -> Result<()> {
+    challenge25()?;
+    challenge26()?;
+    challenge27()?;
 
     Ok(())
 }
@@ -0,0 +1,175 @@
+use crate::{NGram, Result, Unigram};
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::fs;
+use std::sync::OnceLock;
+
+/// Byte n-gram counts of a corpus, compiled once and saved so that scorers can be built without
+/// reading the corpus again.
+///
+/// The binary format is the magic `CPLM`, a version byte, the order byte, the total number of
+/// n-grams as a little endian u64, the number of entries as a little endian u32, then every
+/// entry sorted by n-gram: the n-gram bytes followed by its count as a little endian u32.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LanguageModel {
+    order: usize,
+    total: u64,
+    counts: HashMap<Vec<u8>, u32>,
+}
+
+impl LanguageModel {
+    const MAGIC: &'static [u8] = b"CPLM";
+    const VERSION: u8 = 1;
+
+    /// Counts the n-grams of `order` bytes, which must fit the order byte of the format.
+    pub fn train(corpus: &[u8], order: usize) -> Result<Self> {
+        if !(1..=u8::MAX as usize).contains(&order) {
+            return Err(format!("N-gram order should be between 1 and 255, is {}", order).into());
+        }
+
+        let mut counts: HashMap<Vec<u8>, u32> = HashMap::new();
+        for ngram in corpus.windows(order) {
+            *counts.entry(ngram.to_vec()).or_insert(0) += 1;
+        }
+
+        Ok(LanguageModel {
+            order,
+            total: corpus.len().saturating_sub(order - 1) as u64,
+            counts,
+        })
+    }
+
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn counts(&self) -> &HashMap<Vec<u8>, u32> {
+        &self.counts
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut entries: Vec<_> = self.counts.iter().collect();
+        entries.sort();
+
+        let mut out = Self::MAGIC.to_vec();
+        out.push(Self::VERSION);
+        out.push(self.order as u8);
+        out.extend_from_slice(&self.total.to_le_bytes());
+        out.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        for (ngram, count) in entries {
+            out.extend_from_slice(ngram);
+            out.extend_from_slice(&count.to_le_bytes());
+        }
+
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        if !bytes.starts_with(Self::MAGIC) || bytes.len() < 18 {
+            return Err("Not a compiled language model".into());
+        }
+        if bytes[4] != Self::VERSION {
+            return Err(format!("Unsupported language model version {}", bytes[4]).into());
+        }
+
+        let order = bytes[5] as usize;
+        let total = u64::from_le_bytes(bytes[6..14].try_into()?);
+        let len = u32::from_le_bytes(bytes[14..18].try_into()?) as usize;
+
+        let entries = &bytes[18..];
+        if order == 0 || entries.len() != len * (order + 4) {
+            return Err("Truncated or corrupted language model".into());
+        }
+
+        let counts = entries
+            .chunks(order + 4)
+            .map(|e| {
+                Ok((
+                    e[..order].to_vec(),
+                    u32::from_le_bytes(e[order..].try_into()?),
+                ))
+            })
+            .collect::<Result<HashMap<Vec<u8>, u32>>>()?;
+
+        Ok(LanguageModel {
+            order,
+            total,
+            counts,
+        })
+    }
+
+    pub fn save(&self, path: &str) -> Result<()> {
+        Ok(fs::write(path, self.to_bytes())?)
+    }
+
+    pub fn load(path: &str) -> Result<Self> {
+        Self::from_bytes(&fs::read(path)?)
+    }
+}
+
+/// Languages shipping with compiled models, embedded in the binary and parsed on first use.
+///
+/// English is trained on the books in `data/`, the other languages on the translations of the Vim
+/// tutor in `data/`. Models are rebuilt with the `compile_model` binary, see `models/README`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    English,
+    French,
+    German,
+    Italian,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::English,
+        Language::French,
+        Language::German,
+        Language::Italian,
+    ];
+
+    fn compiled(self, order: usize) -> Result<&'static [u8]> {
+        match (self, order) {
+            (Language::English, 1) => Ok(include_bytes!("../models/en.1.cplm")),
+            (Language::English, 2) => Ok(include_bytes!("../models/en.2.cplm")),
+            (Language::French, 1) => Ok(include_bytes!("../models/fr.1.cplm")),
+            (Language::French, 2) => Ok(include_bytes!("../models/fr.2.cplm")),
+            (Language::German, 1) => Ok(include_bytes!("../models/de.1.cplm")),
+            (Language::German, 2) => Ok(include_bytes!("../models/de.2.cplm")),
+            (Language::Italian, 1) => Ok(include_bytes!("../models/it.1.cplm")),
+            (Language::Italian, 2) => Ok(include_bytes!("../models/it.2.cplm")),
+            _ => Err(format!("No compiled {:?} model of order {}", self, order).into()),
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn unigram(self) -> &'static Unigram {
+        static MODELS: [OnceLock<Unigram>; 4] = [const { OnceLock::new() }; 4];
+
+        MODELS[self.index()].get_or_init(|| {
+            let model = self
+                .compiled(1)
+                .and_then(LanguageModel::from_bytes)
+                .expect("Embedded model");
+            Unigram::from_model(&model).expect("Embedded model")
+        })
+    }
+
+    pub fn bigram(self) -> &'static NGram {
+        static MODELS: [OnceLock<NGram>; 4] = [const { OnceLock::new() }; 4];
+
+        MODELS[self.index()].get_or_init(|| {
+            let model = self
+                .compiled(2)
+                .and_then(LanguageModel::from_bytes)
+                .expect("Embedded model");
+            NGram::from_model(&model)
+        })
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{LanguageModel, Result};
+use std::collections::HashMap;
+use std::fs;
+
+/// Rates how much a candidate plaintext looks like the language a model was trained on, higher
+/// is better. Scores only compare between candidates of the same length.
+pub trait Scorer {
+    fn score(&self, plaintext: &[u8]) -> f32;
+}
+
+/// A byte frequency table, as built by `build_charstat_dict`, sums the frequencies of the bytes.
+impl Scorer for [f32; 256] {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        plaintext.iter().map(|b| self[*b as usize]).sum()
+    }
+}
+
+impl<S: Scorer + ?Sized> Scorer for Box<S> {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        (**self).score(plaintext)
+    }
+}
+
+/// Byte frequencies of a corpus, scoring like a `build_charstat_dict` table.
+pub struct Unigram {
+    freqs: [f32; 256],
+}
+
+impl Unigram {
+    pub fn train(corpus: &[u8]) -> Self {
+        let mut freqs = [0.0f32; 256];
+
+        for b in corpus {
+            freqs[*b as usize] += 1.0;
+        }
+        for f in &mut freqs {
+            *f /= corpus.len().max(1) as f32;
+        }
+
+        Unigram { freqs }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        Ok(Self::train(&fs::read(path)?))
+    }
+
+    pub fn from_model(model: &LanguageModel) -> Result<Self> {
+        if model.order() != 1 {
+            return Err(format!("Expected a unigram model, got order {}", model.order()).into());
+        }
+
+        let mut freqs = [0.0f32; 256];
+        for (ngram, count) in model.counts() {
+            freqs[ngram[0] as usize] = *count as f32 / model.total().max(1) as f32;
+        }
+
+        Ok(Unigram { freqs })
+    }
+
+    pub fn freqs(&self) -> &[f32; 256] {
+        &self.freqs
+    }
+}
+
+impl Scorer for Unigram {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        self.freqs.score(plaintext)
+    }
+}
+
+/// Log-likelihood of the plaintext under the byte n-grams of a corpus. N-grams missing from the
+/// corpus get the probability of half an occurrence, so a single odd sequence does not rule a
+/// candidate out.
+pub struct NGram {
+    order: usize,
+    log_probs: HashMap<Vec<u8>, f32>,
+    unseen: f32,
+}
+
+impl NGram {
+    pub fn train(corpus: &[u8], order: usize) -> Result<Self> {
+        Ok(Self::from_model(&LanguageModel::train(corpus, order)?))
+    }
+
+    pub fn from_model(model: &LanguageModel) -> Self {
+        let total = model.total().max(1) as f32;
+        let log_probs = model
+            .counts()
+            .iter()
+            .map(|(ngram, count)| (ngram.clone(), (*count as f32 / total).ln()))
+            .collect();
+
+        NGram {
+            order: model.order(),
+            log_probs,
+            unseen: (0.5 / total).ln(),
+        }
+    }
+
+    pub fn bigram(corpus: &[u8]) -> Result<Self> {
+        Self::train(corpus, 2)
+    }
+
+    pub fn trigram(corpus: &[u8]) -> Result<Self> {
+        Self::train(corpus, 3)
+    }
+
+    pub fn from_file(path: &str, order: usize) -> Result<Self> {
+        Self::train(&fs::read(path)?, order)
+    }
+}
+
+impl Scorer for NGram {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        plaintext
+            .windows(self.order)
+            .map(|ngram| *self.log_probs.get(ngram).unwrap_or(&self.unseen))
+            .sum()
+    }
+}
+
+/// Chi-squared distance between the byte counts of the plaintext and the ones expected from a
+/// corpus, negated so that closer is higher.
+pub struct ChiSquared {
+    expected: [f32; 256],
+}
+
+impl ChiSquared {
+    /// Bytes never seen in the corpus still get a small expected frequency, so that they weigh
+    /// heavily instead of dividing by zero.
+    const FLOOR: f32 = 1e-5;
+
+    pub fn train(corpus: &[u8]) -> Self {
+        let mut expected = *Unigram::train(corpus).freqs();
+        for e in &mut expected {
+            *e = e.max(Self::FLOOR);
+        }
+
+        ChiSquared { expected }
+    }
+
+    pub fn from_file(path: &str) -> Result<Self> {
+        Ok(Self::train(&fs::read(path)?))
+    }
+}
+
+impl Scorer for ChiSquared {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        let mut observed = [0u32; 256];
+        for b in plaintext {
+            observed[*b as usize] += 1;
+        }
+
+        let len = plaintext.len() as f32;
+        let distance: f32 = observed
+            .iter()
+            .zip(&self.expected)
+            .map(|(&o, &e)| (o as f32 - e * len).powi(2) / (e * len))
+            .sum();
+
+        -distance
+    }
+}
+
+/// Share of printable ASCII, spaces, tabs and line breaks. Needs no corpus, but cannot tell apart
+/// candidates that only differ in case.
+pub struct PrintableRatio;
+
+impl Scorer for PrintableRatio {
+    fn score(&self, plaintext: &[u8]) -> f32 {
+        let printable = plaintext
+            .iter()
+            .filter(|b| b.is_ascii_graphic() || matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+            .count();
+
+        printable as f32 / plaintext.len().max(1) as f32
+    }
+}
@@ -48,9 +48,8 @@ pub fn challenge2() -> Result<()> {
 pub fn challenge3() -> Result<()> {
     let input = "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736";
 
-    // let dict = build_charstat_dict("data/pride_and_prejudice.txt")?;
-    let dict = build_charstat_dict("data/alice_wonderlands.txt")?;
-    let (key, _) = crack_single_xor(&hex::string_to_vec_u8(input)?, &dict)?;
+    let scorer = Language::English.unigram();
+    let (key, _) = crack_single_xor(&hex::string_to_vec_u8(input)?, scorer)?[0];
 
     println!("----- [START] Challenge 3 -----");
     println!(
@@ -70,12 +69,12 @@ pub fn challenge4() -> Result<()> {
     let mut xored_line = String::new();
 
     let file = File::open(path)?;
-    let dict = build_charstat_dict("./data/alice_wonderlands.txt")?;
+    let scorer = Language::English.unigram();
     let reader = BufReader::new(file);
 
     for (line_number, line) in reader.lines().enumerate() {
-        let as_bytes = hex::string_to_vec_u8(&line.as_ref().unwrap())?;
-        let (key, score) = crack_single_xor(&as_bytes, &dict)?;
+        let as_bytes = hex::string_to_vec_u8(line.as_ref().unwrap())?;
+        let (key, score) = crack_single_xor(&as_bytes, scorer)?[0];
         if score > max_score {
             max_score = score;
             xored_line = line.unwrap();
@@ -107,10 +106,7 @@ pub fn challenge5() -> Result<()> {
     println!("----- [START] Challenge 5 -----");
     println!(
         "Answer of Set1 challenge5: {}",
-        hex::vec_u8_to_string(do_vigenere(
-            &input.as_bytes().to_vec(),
-            &key.as_bytes().to_vec()
-        )?)
+        hex::vec_u8_to_string(do_vigenere(input.as_bytes(), key.as_bytes())?)
     );
     println!("----- [END] Challenge 5 -----");
 
@@ -119,11 +115,11 @@ pub fn challenge5() -> Result<()> {
 
 pub fn challenge6() -> Result<()> {
     let input = base64::file_to_vec_u8("./data/set_1_challenge_6.txt")?;
-    let key = crack_vigenere(&input)?;
+    let (key, _) = crack_vigenere(&input, Language::English.unigram())?.remove(0);
     let plain = do_vigenere(&input, &key)?;
 
     println!("----- [START] Challenge 6 -----");
-    println!("Key: {}", str::from_utf8(&key).to_owned()?.to_string());
+    println!("Key: {}", str::from_utf8(&key)?);
     println!(
         "Decrypted: {}...",
         str::from_utf8(&plain.as_slice()[0..20])?
@@ -156,7 +152,7 @@ pub fn challenge8() -> Result<()> {
     println!("----- [START] Challenge 8 -----");
     for (line_number, line) in reader.lines().map(|line| line.unwrap()).enumerate() {
         let as_bytes = hex::string_to_vec_u8(&line)?;
-        if detect_ecb(&as_bytes) == true {
+        if detect_ecb(&as_bytes) {
             println!(
                 "Line #{} contains duplicate blocks: {}",
                 line_number + 1,
@@ -5,7 +5,7 @@ extern crate cookie_oracle;
 extern crate hex;
 
 use aes::padding_pkcs7;
-use oracle::Oracle;
+use oracle::{CountingOracle, EncryptionOracle};
 use rust_cryptopals::*;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
@@ -38,7 +38,7 @@ pub fn challenge10() -> Result<()> {
 }
 
 pub fn challenge11() -> Result<()> {
-    let oracle = aes_oracle::new(None, None);
+    let oracle = aes_oracle::new(None, None)?;
 
     let mode = detect_encryption_mode(&oracle)?;
 
@@ -53,10 +53,10 @@ pub fn challenge11() -> Result<()> {
 pub fn challenge12() -> Result<()> {
     let suffix = base64::string_to_vec_u8("Um9sbGluJyBpbiBteSA1LjAKV2l0aCBteSByYWctdG9wIGRvd24gc28gbXkgaGFpciBjYW4gYmxvdwpUaGUgZ2lybGllcyBvbiBzdGFuZGJ5IHdhdmluZyBqdXN0IHRvIHNheSBoaQpEaWQgeW91IHN0b3A/IE5vLCBJIGp1c3QgZHJvdmUgYnkK")?;
 
-    let oracle = aes_oracle::new(Some(aes::MODE::ECB), Some(&suffix));
+    let oracle = CountingOracle::new(aes_oracle::new(Some(aes::MODE::ECB), Some(&suffix))?);
 
     println!("---- [START] Challenge 12 ----");
-    println!("Oracle:\n{}", oracle);
+    println!("Oracle:\n{}", oracle.inner());
 
     let block_size = detect_blocksize(&oracle)?;
     println!("Detected block size: {}", block_size);
@@ -76,13 +76,14 @@ pub fn challenge12() -> Result<()> {
         "Answer of Set 2 Challenge 12: {}...",
         String::from_utf8(plaintext.as_slice()[0..20].to_vec())?
     );
+    println!("Oracle usage: {}", oracle.stats());
     println!("---- [END] Challenge 12 ----");
 
     Ok(())
 }
 
 pub fn challenge13() -> Result<()> {
-    let oracle = cookie_oracle::ProfileOracle::new();
+    let oracle = cookie_oracle::ProfileOracle::new()?;
 
     println!("---- [START] Challenge 13 ----");
 
@@ -117,7 +118,7 @@ pub fn challenge13() -> Result<()> {
     forged_profile[chunks_count * block_size..].copy_from_slice(target_last_block);
 
     let ex_cipher = &oracle.encrypt(b"user420@example.com")?;
-    let ex_profile = &oracle.profile_from_encrypted(&ex_cipher)?;
+    let ex_profile = &oracle.profile_from_encrypted(ex_cipher)?;
 
     println!("Example profile:\n{}", ex_profile);
 
@@ -129,3 +130,56 @@ pub fn challenge13() -> Result<()> {
     println!("---- [END] Challenge 13 ----");
     Ok(())
 }
+
+pub fn challenge14() -> Result<()> {
+    let oracle = CountingOracle::new(aes_oracle::OracleChallenge14::new()?);
+
+    println!("---- [START] Challenge 14 ----");
+
+    let block_size = detect_blocksize(&oracle)?;
+    println!("Detected block size: {}", block_size);
+
+    let prefix_len = detect_prefix_len(&oracle)?;
+    println!("Prefix length: {}", prefix_len);
+
+    let plaintext = recover_ecb_suffix_with_prefix(&oracle)?;
+
+    println!(
+        "Answer of Set 2 Challenge 14: {}...",
+        String::from_utf8(plaintext.as_slice()[0..20].to_vec())?
+    );
+    println!("Oracle usage: {}", oracle.stats());
+    println!("---- [END] Challenge 14 ----");
+
+    Ok(())
+}
+
+pub fn challenge16() -> Result<()> {
+    let oracle = cookie_oracle::CommentOracle::new()?;
+    let target = b";admin=true;";
+
+    println!("---- [START] Challenge 16 ----");
+
+    let block_size = detect_blocksize(&oracle)?;
+    let prefix_len = detect_prefix_len(&oracle)?;
+    println!("Prefix length: {}", prefix_len);
+
+    // Complete the last block of the prefix, then send a block to scramble and one to rewrite
+    let (prefix_blocks, fill_len) = aes::blocks_and_padding(prefix_len, block_size);
+    let known = vec![b'A'; fill_len + 2 * block_size];
+    let cipher = oracle.encrypt(&known)?;
+
+    let forged = cbc_bitflip(
+        &cipher,
+        block_size,
+        prefix_blocks + 1,
+        &known[..target.len()],
+        target,
+    )?;
+
+    println!("Plain cipher is admin: {}", oracle.is_admin(&cipher)?);
+    println!("Forged cipher is admin: {}", oracle.is_admin(&forged)?);
+    println!("---- [END] Challenge 16 ----");
+
+    Ok(())
+}
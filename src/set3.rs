@@ -1,20 +1,34 @@
 extern crate aes;
 extern crate oracle;
 
-use crate::set3::oracle::Oracle;
+use oracle::EncryptionOracle;
+use rust_cryptopals::*;
+use std::collections::BTreeSet;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
 pub fn challenge17() -> Result<()> {
     let oracle = oracle::OracleChallenge17::new()?;
 
-    let payload = vec![0];
-
-    let mut cipher = oracle.encrypt(&payload)?;
-
-    cipher = cipher[cipher.len() - 32..].to_vec();
-
-    println!("Valid padding: {}", oracle.check_cipher_padding(&cipher)?);
+    println!("---- [START] Challenge 17 ----");
+
+    // Each encryption picks one of the ten strings at random, decrypt until all were seen
+    let mut strings = BTreeSet::new();
+    for _ in 0..500 {
+        let cipher = oracle.encrypt(&[])?;
+        let mut plaintext = padding_oracle_decrypt(&oracle, &cipher, 16)?;
+        aes::unpad_pkcs7(&mut plaintext, 16)?;
+        strings.insert(String::from_utf8(plaintext)?);
+
+        if strings.len() == 10 {
+            break;
+        }
+    }
+
+    for s in &strings {
+        println!("{}", s);
+    }
+    println!("---- [END] Challenge 17 ----");
 
     Ok(())
 }
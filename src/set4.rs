@@ -0,0 +1,72 @@
+extern crate aes;
+extern crate cookie_oracle;
+extern crate oracle;
+
+use oracle::EncryptionOracle;
+use rust_cryptopals::*;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+pub fn challenge25() -> Result<()> {
+    // The challenge file is the one of challenge 7
+    let input = base64::file_to_vec_u8("data/set_1_challenge_7.txt")?;
+    let mut plaintext = aes::decrypt_aes_128_ecb(&input, b"YELLOW SUBMARINE")?;
+    aes::unpad_pkcs7(&mut plaintext, 16)?;
+
+    let oracle = oracle::OracleChallenge25::new(&plaintext)?;
+
+    println!("---- [START] Challenge 25 ----");
+
+    let recovered = recover_plaintext_with_edit(&oracle, oracle.cipher())?;
+
+    println!(
+        "Answer of Set 4 Challenge 25: {}...",
+        String::from_utf8(recovered[0..33].to_vec())?
+    );
+    println!("Recovered the whole plaintext: {}", recovered == plaintext);
+    println!("---- [END] Challenge 25 ----");
+
+    Ok(())
+}
+
+pub fn challenge26() -> Result<()> {
+    let oracle = cookie_oracle::CommentOracle::ctr()?;
+    let target = b";admin=true;";
+
+    println!("---- [START] Challenge 26 ----");
+
+    let prefix_len = detect_prefix_len(&oracle)?;
+    println!("Prefix length: {}", prefix_len);
+
+    let known = vec![b'A'; target.len()];
+    let cipher = oracle.encrypt(&known)?;
+    let forged = ctr_bitflip(&cipher, prefix_len, &known, target)?;
+
+    println!("Plain cipher is admin: {}", oracle.is_admin(&cipher)?);
+    println!("Forged cipher is admin: {}", oracle.is_admin(&forged)?);
+    println!("---- [END] Challenge 26 ----");
+
+    Ok(())
+}
+
+pub fn challenge27() -> Result<()> {
+    let oracle = oracle::OracleChallenge27::new()?;
+
+    println!("---- [START] Challenge 27 ----");
+
+    let key = recover_key_as_iv(&oracle, 16)?;
+    println!("Recovered key: {}", hex::vec_u8_to_string(key.clone()));
+
+    let message = b"Key and IV should never be the same";
+    let cipher = oracle.encrypt(message)?;
+    let mut plaintext = aes::decrypt_aes_128_cbc(&cipher, &key, &key)?;
+    aes::unpad_pkcs7(&mut plaintext, 16)?;
+
+    println!(
+        "Decrypted with the recovered key: {}",
+        String::from_utf8(plaintext)?
+    );
+    println!("---- [END] Challenge 27 ----");
+
+    Ok(())
+}
@@ -1,6 +1,12 @@
 use rust_cryptopals::*;
+#[allow(dead_code)]
 mod set1;
+#[allow(dead_code)]
 mod set2;
+#[allow(dead_code)]
+mod set3;
+#[allow(dead_code)]
+mod set4;
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync + 'static>>;
 
@@ -14,9 +20,412 @@ mod tests_set1 {
 
         Ok(())
     }
+
+    #[test]
+    fn test_scorers_rank_single_xor_key() -> Result<()> {
+        let corpus = std::fs::read("./data/pride_and_prejudice.txt")?;
+        let input = hex::string_to_vec_u8(
+            "1b37373331363f78151b7f2b783431333d78397828372d363c78373e783a393b3736",
+        )?;
+
+        let scorers: Vec<Box<dyn Scorer>> = vec![
+            Box::new(Unigram::train(&corpus)),
+            Box::new(NGram::bigram(&corpus)?),
+            Box::new(NGram::trigram(&corpus)?),
+            Box::new(ChiSquared::train(&corpus)),
+        ];
+        for scorer in &scorers {
+            assert_eq!(crack_single_xor(&input, scorer)?[0].0, b'X');
+        }
+
+        // Many keys decrypt to printable text, the right one is among them
+        let ranked = crack_single_xor(&input, &PrintableRatio)?;
+        assert!(ranked.iter().any(|(k, s)| *k == b'X' && *s == ranked[0].1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_language_model_round_trip() -> Result<()> {
+        let corpus = std::fs::read("./data/alice_wonderlands.txt")?;
+        let model = LanguageModel::train(&corpus, 2)?;
+
+        let bytes = model.to_bytes();
+        assert_eq!(LanguageModel::from_bytes(&bytes)?, model);
+        assert!(LanguageModel::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Unigram::from_model(&model).is_err());
+        assert!(LanguageModel::train(&corpus, 0).is_err());
+        assert!(LanguageModel::train(&corpus, 256).is_err());
+        assert!(NGram::train(&corpus, 0).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crack_vigenere_non_english() -> Result<()> {
+        let plaintext =
+            "Die Würde des Menschen ist unantastbar. Sie zu achten und zu schützen ist \
+                         Verpflichtung aller staatlichen Gewalt. Das Deutsche Volk bekennt sich \
+                         darum zu unverletzlichen und unveräußerlichen Menschenrechten als \
+                         Grundlage jeder menschlichen Gemeinschaft, des Friedens und der \
+                         Gerechtigkeit in der Welt.";
+        let cipher = do_vigenere(plaintext.as_bytes(), b"Schl\xc3\xbcssel")?;
+
+        // The key may come out repeated, which decrypts all the same
+        let (key, _) = crack_vigenere(&cipher, Language::German.bigram())?.remove(0);
+        assert!(key.starts_with("Schlüssel".as_bytes()));
+        assert_eq!(do_vigenere(&cipher, &key)?, plaintext.as_bytes());
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests_set2 {
     use super::*;
+    use oracle::{
+        AesOracle, CountingOracle, EncryptionOracle, NoisyOracle, OracleChallenge12,
+        OracleChallenge14, RateLimitedOracle, RecordingOracle, ReplayOracle, VerificationOracle,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_recover_ecb_suffix_query_count() -> Result<()> {
+        let oracle = CountingOracle::new(OracleChallenge12::with_seed(12)?);
+
+        let plaintext = recover_ecb_suffix(&oracle)?;
+
+        assert!(plaintext.starts_with(b"Rollin' in my 5.0"));
+        // One dictionary and one lookup per byte, plus a few queries to measure the oracle
+        assert!(oracle.stats().encrypt_calls <= plaintext.len() * 257 + 50);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_ecb_suffix_dynamic_oracles() -> Result<()> {
+        let closure = |data: &[u8]| -> Result<Vec<u8>> {
+            let mut input = data.to_vec();
+            input.extend_from_slice(b"Closure backed secret suffix");
+            aes::encrypt_aes_128_ecb(&input, b"YELLOW SUBMARINE")
+        };
+        assert!(recover_ecb_suffix(&closure)?.starts_with(b"Closure backed"));
+
+        let boxed: Box<dyn EncryptionOracle> = Box::new(OracleChallenge12::with_seed(12)?);
+        assert_eq!(detect_blocksize(&boxed)?, 16);
+
+        let shared: Box<dyn EncryptionOracle + Send + Sync> =
+            Box::new(OracleChallenge12::with_seed(12)?);
+        assert_eq!(detect_blocksize(&shared)?, 16);
+
+        let borrowed: &dyn EncryptionOracle = &*boxed;
+        assert!(recover_ecb_suffix(borrowed)?.starts_with(b"Rollin' in my 5.0"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_ecb_suffix_replayed() -> Result<()> {
+        let mut transcript = Vec::new();
+        let recorder = RecordingOracle::new(OracleChallenge12::with_seed(12)?, &mut transcript);
+        let plaintext = recover_ecb_suffix(&recorder)?;
+        drop(recorder);
+
+        let replay = ReplayOracle::from_reader(&transcript[..])?;
+        assert_eq!(recover_ecb_suffix(&replay)?, plaintext);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_ecb_suffix_with_prefix() -> Result<()> {
+        let oracle = OracleChallenge14::with_seed(14)?;
+        assert!(recover_ecb_suffix_with_prefix(&oracle)?.starts_with(b"Rollin' in my 5.0"));
+
+        // Toy 8 bytes block cipher behind a 13 bytes prefix
+        let closure = |data: &[u8]| -> Result<Vec<u8>> {
+            let mut input = b"13 bytes long".to_vec();
+            input.extend_from_slice(data);
+            input.extend_from_slice(b"Secret behind a prefix");
+            aes::padding_pkcs7(&mut input, 8)?;
+
+            let mut cipher = Vec::new();
+            for block in input.chunks(8) {
+                let mut wide = block.to_vec();
+                wide.resize(16, 0);
+                cipher.extend_from_slice(
+                    &aes::encrypt_aes_128_block(&wide, b"YELLOW SUBMARINE")?[..8],
+                );
+            }
+            Ok(cipher)
+        };
+        assert_eq!(detect_prefix_len(&closure)?, 13);
+        assert_eq!(
+            recover_ecb_suffix_with_prefix(&closure)?,
+            b"Secret behind a prefix"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_ecb_suffix_random_prefix() -> Result<()> {
+        let secret = b"Prefix drawn again on every single call".to_vec();
+        let oracle = AesOracle::builder()
+            .seed(44)
+            .mode(aes::MODE::ECB)
+            .random_prefix_per_call(0..40)
+            .suffix(secret.clone())
+            .build()?;
+
+        assert_eq!(recover_ecb_suffix_random_prefix(&oracle, 16)?, secret);
+
+        // Suffixes starting with a sentinel byte must not shift the alignment
+        for first in [0x00, 0xff] {
+            let mut secret = vec![first; 2];
+            secret.extend_from_slice(b"Suffix led by a sentinel byte");
+            let oracle = AesOracle::builder()
+                .seed(first as u64)
+                .mode(aes::MODE::ECB)
+                .random_prefix_per_call(0..40)
+                .suffix(secret.clone())
+                .build()?;
+
+            // Without the prefix, the suffix always encrypts to the same blocks
+            let sentinel_blocks = sentinel_cipher_blocks(&oracle, 16)?;
+            let reference = encrypt_aligned(&oracle, &[], &sentinel_blocks, 16)?;
+            for _ in 0..50 {
+                assert_eq!(
+                    encrypt_aligned(&oracle, &[], &sentinel_blocks, 16)?,
+                    reference
+                );
+            }
+
+            let aligned = |data: &[u8]| encrypt_aligned(&oracle, data, &sentinel_blocks, 16);
+            assert_eq!(detect_suffix_len(&aligned)?, secret.len());
+            assert_eq!(recover_ecb_suffix_random_prefix(&oracle, 16)?, secret);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cbc_bitflip() -> Result<()> {
+        let oracle = cookie_oracle::CommentOracle::with_seed(16)?;
+        // The prefix fills two blocks, the input the next two
+        let cipher = oracle.encrypt(&[b'A'; 32])?;
+
+        let forged = cbc_bitflip(&cipher, 16, 3, &[b'A'; 12], b";admin=true;")?;
+
+        assert!(!oracle.verify(&cipher)?);
+        assert!(oracle.verify(&forged)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detection_against_noisy_oracle() -> Result<()> {
+        let oracle = OracleChallenge14::with_seed(14)?;
+        let expected = detect_prefix_blocks_count(&oracle)?;
+        let prefix_len = detect_prefix_len(&oracle)?;
+        let suffix_len = detect_suffix_len(&oracle)?;
+        let both_len = detect_prefix_plus_suffix_len(&oracle)?;
+
+        let noisy = RateLimitedOracle::new(
+            NoisyOracle::new(oracle)?
+                .seed(39)
+                .error_rate(0.3)
+                .latency(Duration::ZERO..Duration::from_micros(200)),
+            20,
+            5000.0,
+        );
+
+        assert_eq!(detect_blocksize(&noisy)?, 16);
+        assert_eq!(detect_prefix_blocks_count(&noisy)?, expected);
+        assert_eq!(detect_prefix_len(&noisy)?, prefix_len);
+        assert_eq!(detect_suffix_len(&noisy)?, suffix_len);
+        assert_eq!(detect_prefix_plus_suffix_len(&noisy)?, both_len);
+        assert!(detect_padding(&noisy)?);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests_set3 {
+    use super::*;
+    use oracle::{EncryptionOracle, OracleChallenge17, PaddingOracle};
+
+    #[test]
+    fn test_padding_oracle_decrypt() -> Result<()> {
+        let oracle = OracleChallenge17::with_seed(17)?;
+        let cipher = oracle.encrypt(&[])?;
+
+        let mut plaintext = padding_oracle_decrypt(&oracle, &cipher, 16)?;
+        assert_eq!(plaintext.len(), cipher.len() - 16);
+
+        aes::unpad_pkcs7(&mut plaintext, 16)?;
+        assert!(plaintext.starts_with(b"00000"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_padding_oracle_encrypt() -> Result<()> {
+        let oracle = OracleChallenge17::with_seed(17)?;
+        let message = b"Forged with nothing but a padding oracle;admin=true";
+
+        let cipher = padding_oracle_encrypt(&oracle, message, 16)?;
+        assert!(oracle.check_cipher_padding(&cipher)?);
+
+        let mut plaintext = oracle.reveal_plaintext(&cipher)?;
+        aes::unpad_pkcs7(&mut plaintext, 16)?;
+        assert_eq!(plaintext, message);
+
+        Ok(())
+    }
+
+    const KEY: &[u8] = b"YELLOW SUBMARINE";
+
+    /// Lines of English text, encrypted under the same key and nonce.
+    fn fixed_nonce_ciphers(lines: &[Vec<u8>]) -> Result<Vec<Vec<u8>>> {
+        lines
+            .iter()
+            .map(|l| aes::encrypt_aes_ctr(l, KEY, 0))
+            .collect()
+    }
+
+    fn english_lines() -> Result<Vec<Vec<u8>>> {
+        let text = std::fs::read_to_string("./data/alice_wonderlands.txt")?;
+
+        Ok(text
+            .lines()
+            .skip(100)
+            .filter(|l| l.len() >= 30)
+            .take(40)
+            .map(|l| l.as_bytes().to_vec())
+            .collect())
+    }
+
+    #[test]
+    fn test_crack_fixed_nonce() -> Result<()> {
+        let dict = build_charstat_dict("./data/pride_and_prejudice.txt")?;
+        let ciphers = fixed_nonce_ciphers(&english_lines()?)?;
+        let keystream = aes::encrypt_aes_ctr(&[0; 128], KEY, 0)?;
+
+        let truncated = crack_fixed_nonce(&ciphers, &dict, true)?;
+        let correct = truncated
+            .iter()
+            .zip(&keystream)
+            .filter(|((k, _), t)| k == *t)
+            .count();
+        assert!(correct >= truncated.len() * 9 / 10);
+
+        let ragged = crack_fixed_nonce(&ciphers, &dict, false)?;
+        assert!(ragged.len() > truncated.len());
+        assert!(ragged[ragged.len() - 1].1 < ragged[0].1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crib_dragger() -> Result<()> {
+        let dict = build_charstat_dict("./data/pride_and_prejudice.txt")?;
+        let lines = english_lines()?;
+        let ciphers = fixed_nonce_ciphers(&lines)?;
+        let mut dragger = CribDragger::new(ciphers, Vec::new());
+
+        let best = dragger.drag(&lines[1][..12], &dict);
+        assert!(best[..3].iter().any(|m| m.cipher == 1 && m.offset == 0));
+
+        let commands = format!("fix 0 0 {}\nquit\n", String::from_utf8(lines[0].clone())?);
+        let mut output = Vec::new();
+        dragger.interact(&dict, commands.as_bytes(), &mut output)?;
+
+        assert_eq!(dragger.plaintexts()[0], lines[0]);
+        assert!(
+            dragger.plaintexts()[1].starts_with(&lines[1][..lines[0].len().min(lines[1].len())])
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests_set4 {
+    use super::*;
+    use oracle::{EncryptionOracle, OracleChallenge25, OracleChallenge27};
+
+    #[test]
+    fn test_recover_plaintext_with_edit() -> Result<()> {
+        let plaintext = b"Edit me as much as you like, the keystream comes right back";
+        let oracle = OracleChallenge25::new(plaintext)?;
+
+        assert_eq!(
+            recover_plaintext_with_edit(&oracle, oracle.cipher())?,
+            plaintext
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_recover_key_as_iv() -> Result<()> {
+        let oracle = OracleChallenge27::with_seed(27)?;
+        let cipher = oracle.encrypt(b"Attack at dawn")?;
+
+        let key = recover_key_as_iv(&oracle, 16)?;
+
+        let mut plaintext = aes::decrypt_aes_128_cbc(&cipher, &key, &key)?;
+        aes::unpad_pkcs7(&mut plaintext, 16)?;
+        assert_eq!(plaintext, b"Attack at dawn");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ctr_bitflip() -> Result<()> {
+        let oracle = cookie_oracle::CommentOracle::ctr_with_seed(26)?;
+        // "comment1=cooking%20MCs;userdata=" is 32 bytes long
+        let cipher = oracle.encrypt(&[b'A'; 12])?;
+
+        let forged = ctr_bitflip(&cipher, 32, &[b'A'; 12], b";admin=true;")?;
+
+        assert!(!oracle.is_admin(&cipher)?);
+        assert!(oracle.is_admin(&forged)?);
+
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "async"))]
+mod tests_async {
+    use super::*;
+    use oracle::{Blocking, NoisyOracle, OracleChallenge12};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_recover_ecb_suffix_async() -> Result<()> {
+        let oracle = Arc::new(OracleChallenge12::with_seed(12)?);
+
+        let plaintext = recover_ecb_suffix_async(&Blocking::new(oracle.clone()), 16).await?;
+
+        assert_eq!(plaintext, recover_ecb_suffix(&*oracle)?);
+        // No concurrency still sends the queries one at a time
+        assert_eq!(
+            recover_ecb_suffix_async(&Blocking::new(oracle.clone()), 0).await?,
+            plaintext
+        );
+
+        // Transient errors are retried like in the synchronous attack
+        let noisy = NoisyOracle::new(OracleChallenge12::with_seed(12)?)?
+            .seed(40)
+            .error_rate(0.2);
+        assert_eq!(
+            recover_ecb_suffix_async(&Blocking::new(Arc::new(noisy)), 16).await?,
+            plaintext
+        );
+
+        Ok(())
+    }
 }